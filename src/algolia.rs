@@ -0,0 +1,241 @@
+//! [Algolia HN Search API][algolia-api] indexes all HN stories including
+//! which of them made it to the front page. Firebase APIs only give us the
+//! current top stories, therefore we use Algolia to backfill the database with
+//! historical front page stories.
+//!
+//! [algolia-api]: https://hn.algolia.com/api
+
+use {
+    reqwest::Client, rusqlite::Connection, serde::Deserialize,
+    std::collections::HashSet,
+};
+
+use crate::{date::SECS_IN_DAY, db, filter, prelude::*};
+
+pub const ALGOLIA_API: &str = "https://hn.algolia.com/api/v1";
+
+// Algolia returns at most 1000 hits per query no matter how many pages we
+// request. A single day of front page stories is well below that.
+const WINDOW_SECS: i64 = SECS_IN_DAY;
+
+const HITS_PER_PAGE: usize = 100;
+
+/// Fetches stories which were on the front page and were submitted within
+/// the given range of unix times (`from` inclusive, `to` exclusive), runs them
+/// through the filters and stores those we don't have yet. Stories are fetched
+/// in daily windows, oldest first, and each page is stored as soon as it
+/// arrives so that a failure doesn't lose what was already imported. Returns
+/// how many stories were inserted.
+pub async fn import(
+    conn: &Connection,
+    client: &Client,
+    api: &str,
+    from: i64,
+    to: i64,
+) -> Result<usize> {
+    let mut inserted = 0;
+
+    let mut window_start = from;
+    while window_start < to {
+        let window_end = (window_start + WINDOW_SECS).min(to);
+        log::debug!("Fetching stories from {} to {}", window_start, window_end);
+
+        let mut page = 0;
+        loop {
+            let resp =
+                fetch_page(client, api, window_start, window_end, page).await?;
+            let stories = resp.hits.into_iter().filter_map(Hit::into_story);
+            inserted += insert_new_stories(conn, stories.collect())?;
+
+            page += 1;
+            if page >= resp.nb_pages {
+                break;
+            }
+        }
+
+        window_start = window_end;
+    }
+
+    Ok(inserted)
+}
+
+// Stores those of the stories which aren't in the database yet.
+fn insert_new_stories(conn: &Connection, stories: Vec<Story>) -> Result<usize> {
+    let ids: Vec<_> = stories.iter().map(|story| story.id).collect();
+    let new_ids: HashSet<_> =
+        db::only_new_stories(conn, &ids)?.into_iter().collect();
+    let new_stories: Vec<_> = stories
+        .into_iter()
+        .filter(|story| new_ids.contains(&story.id))
+        .collect();
+
+    let new_stories_filters = filter::for_stories(&new_stories);
+    let inserted = new_stories.len();
+
+    db::insert_stories(conn, new_stories)?;
+    db::insert_filters(conn, &new_stories_filters)?;
+
+    Ok(inserted)
+}
+
+// {
+//     "hits": [{ "objectID": "1", "title": "...", "url": "...", ... }],
+//     "nbPages": 1
+// }
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResponse {
+    hits: Vec<Hit>,
+    nb_pages: usize,
+}
+
+#[derive(Deserialize)]
+struct Hit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+    title: Option<String>,
+    url: Option<String>,
+    story_text: Option<String>,
+    created_at_i: Option<i64>,
+    points: Option<i64>,
+    num_comments: Option<i64>,
+}
+
+impl Hit {
+    // Stories without title or with non numeric id are skipped.
+    fn into_story(self) -> Option<Story> {
+        let id = self.object_id.parse().ok()?;
+        let title = self.title?;
+
+        // some stories have neither url nor text, such as polls
+        let kind = match (self.url, self.story_text) {
+            (Some(url), _) if !url.is_empty() => StoryKind::Url(url),
+            (_, text) => StoryKind::Text(text.unwrap_or_default()),
+        };
+
+        Some(Story {
+            id,
+            title,
+            submitted_at: self.created_at_i,
+            score: self.points,
+            comments: self.num_comments,
+            archives: vec![],
+            article: None,
+            kind,
+        })
+    }
+}
+
+// Returns single page of front page stories submitted in the given window.
+async fn fetch_page(
+    client: &Client,
+    api: &str,
+    from: i64,
+    to: i64,
+    page: usize,
+) -> Result<SearchResponse> {
+    let numeric_filters = format!("created_at_i>={},created_at_i<{}", from, to);
+
+    let resp = client
+        .get(format!("{}/search_by_date", api))
+        .query(&[
+            ("tags", "front_page"),
+            ("numericFilters", &numeric_filters),
+            ("hitsPerPage", &HITS_PER_PAGE.to_string()),
+            ("page", &page.to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(resp)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stand_in::{Response, StandIn},
+    };
+
+    #[tokio::test]
+    async fn it_imports_front_page_stories() -> Result<()> {
+        let stand_in = StandIn::start(|req| {
+            let body = if req.path.ends_with("&page=1") {
+                r#"{ "nbPages": 2, "hits": [
+                    { "objectID": "3", "title": "Ask HN: Anyone?",
+                      "url": null, "story_text": "Hello",
+                      "created_at_i": 1609459300 }
+                ] }"#
+            } else {
+                r#"{ "nbPages": 2, "hits": [
+                    { "objectID": "1", "title": "Pure Google mate",
+                      "url": "https://bbc.com/news", "story_text": null,
+                      "created_at_i": 1609459200 },
+                    { "objectID": "2", "title": null, "url": null,
+                      "story_text": null, "created_at_i": 1609459250 }
+                ] }"#
+            };
+            Response::ok(body)
+        });
+        let conn = db::tests::test_conn()?;
+        let client = Client::new();
+
+        let day = 1609459200;
+        let inserted =
+            import(&conn, &client, &stand_in.url, day, day + SECS_IN_DAY)
+                .await?;
+        assert_eq!(2, inserted);
+
+        let requests = stand_in.requests();
+        assert_eq!(2, requests.len());
+        assert!(requests[0].path.starts_with("/search_by_date?"));
+        assert!(requests[0].path.contains("tags=front_page"));
+
+        let story = db::select_story(&conn, 1)?.unwrap();
+        assert_eq!("https://bbc.com/news", story.url);
+        assert!(story.filters.contains(&FilterKind::BigTech));
+        assert!(story.filters.contains(&FilterKind::LargeNewspaper));
+
+        let story = db::select_story(&conn, 3)?.unwrap();
+        assert!(story.filters.contains(&FilterKind::AskHn));
+
+        // importing the same range again doesn't duplicate stories
+        let inserted =
+            import(&conn, &client, &stand_in.url, day, day + SECS_IN_DAY)
+                .await?;
+        assert_eq!(0, inserted);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_keeps_imported_pages_on_failure() -> Result<()> {
+        let day = 1609459200;
+        let stand_in = StandIn::start(move |req| {
+            // the second day fails
+            if req.path.contains(&format!("created_at_i%3E%3D{}", day)) {
+                Response::ok(
+                    r#"{ "nbPages": 1, "hits": [
+                        { "objectID": "1", "title": "First day",
+                          "url": "https://example.org", "story_text": null,
+                          "created_at_i": 1609459200 }
+                    ] }"#,
+                )
+            } else {
+                Response::status(500, "")
+            }
+        });
+        let conn = db::tests::test_conn()?;
+
+        let to = day + 2 * SECS_IN_DAY;
+        assert!(import(&conn, &Client::new(), &stand_in.url, day, to)
+            .await
+            .is_err());
+        assert!(db::select_story(&conn, 1)?.is_some());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! [archive.today][archive-today] (also archive.ph or archive.is) often has
+//! snapshots of pages which Wayback machine cannot capture. It doesn't have an
+//! availability API, but it lists all snapshots of a url in a
+//! [memento timemap][memento].
+//!
+//! [archive-today]: https://archive.ph
+//! [memento]: https://datatracker.ietf.org/doc/html/rfc7089
+
+use {
+    reqwest::{Client, StatusCode},
+    std::time::Duration,
+};
+
+use crate::{conf, date, prelude::*};
+
+const ARCHIVE_TODAY_API: &str = "https://archive.ph";
+
+/// archive.today client.
+pub struct ArchiveToday {
+    client: Client,
+    // base url of the timemap API
+    api: String,
+    // snapshots taken longer than this before or after the submission are
+    // ignored
+    max_age: Duration,
+}
+
+impl ArchiveToday {
+    pub const NAME: &'static str = "archive.today";
+
+    pub fn new(client: Client, conf: &conf::Conf) -> Self {
+        Self::with_api(client, ARCHIVE_TODAY_API, conf.archive_max_age)
+    }
+
+    pub fn with_api(client: Client, api: &str, max_age: Duration) -> Self {
+        Self {
+            client,
+            api: api.to_string(),
+            max_age,
+        }
+    }
+
+    /// Returns the snapshot of given url which is the closest to the
+    /// submission time, unless it was taken too long before or after it.
+    pub async fn find(
+        &self,
+        url: &str,
+        submitted_at: i64,
+    ) -> Result<Option<ArchiveLink>> {
+        let resp = self
+            .client
+            .get(format!("{}/timemap/{}", self.api, url))
+            .send()
+            .await?;
+        // there's no snapshot of the url
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let timemap = resp.error_for_status()?.text().await?;
+
+        let closest = parse_timemap(&timemap)
+            .into_iter()
+            .min_by_key(|(_, archived_at)| (submitted_at - archived_at).abs());
+        let (snapshot_url, archived_at) = match closest {
+            Some(closest) => closest,
+            None => return Ok(None),
+        };
+
+        if !super::is_close_to_submission(
+            archived_at,
+            submitted_at,
+            self.max_age,
+        ) {
+            log::debug!(
+                "Ignoring snapshot {} of {} taken too far from the submission",
+                snapshot_url,
+                url
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(ArchiveLink {
+            provider: Self::NAME.to_string(),
+            url: snapshot_url,
+            archived_at: Some(archived_at),
+        }))
+    }
+}
+
+// Returns urls of snapshots listed in the timemap along with the unix time of
+// when they were taken. The timemap has one link per line:
+//
+// <https://archive.ph/20210101000000/https://example.com>; rel="memento"; ...
+//
+// Besides mementos the timemap links to the original url and to itself. The
+// snapshot time is part of the memento url.
+fn parse_timemap(timemap: &str) -> Vec<(String, i64)> {
+    timemap
+        .lines()
+        .filter(|line| {
+            // first and last mementos have rel="first memento" etc.
+            line.split("rel=\"")
+                .nth(1)
+                .and_then(|rel| rel.split('"').next())
+                .map(|rel| rel.split_whitespace().any(|r| r == "memento"))
+                .unwrap_or(false)
+        })
+        .filter_map(|line| {
+            let url = line.split('<').nth(1)?.split('>').next()?;
+            let timestamp = url.split('/').find(|segment| {
+                segment.len() == 14
+                    && segment.chars().all(|c| c.is_ascii_digit())
+            })?;
+            let archived_at = date::parse_wayback_timestamp(timestamp).ok()?;
+
+            Some((url.to_string(), archived_at))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stand_in::{Response, StandIn},
+    };
+
+    const TIMEMAP: &str = r#"<https://example.com>; rel="original",
+<https://archive.ph/timemap/https://example.com>; rel="self"; type="application/link-format",
+<https://archive.ph/20150101000000/https://example.com>; rel="first memento"; datetime="Thu, 01 Jan 2015 00:00:00 GMT",
+<https://archive.ph/20210101000000/https://example.com>; rel="memento"; datetime="Fri, 01 Jan 2021 00:00:00 GMT",
+<https://archive.ph/20210301000000/https://example.com>; rel="last memento"; datetime="Mon, 01 Mar 2021 00:00:00 GMT""#;
+
+    #[test]
+    fn it_parses_timemap() -> Result<()> {
+        assert_eq!(
+            vec![
+                (
+                    "https://archive.ph/20150101000000/https://example.com"
+                        .to_string(),
+                    date::parse_day("2015-01-01")?
+                ),
+                (
+                    "https://archive.ph/20210101000000/https://example.com"
+                        .to_string(),
+                    date::parse_day("2021-01-01")?
+                ),
+                (
+                    "https://archive.ph/20210301000000/https://example.com"
+                        .to_string(),
+                    date::parse_day("2021-03-01")?
+                ),
+            ],
+            parse_timemap(TIMEMAP)
+        );
+        assert!(parse_timemap("").is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_finds_closest_snapshot() -> Result<()> {
+        let stand_in = StandIn::start(|req| {
+            if req.path.contains("missing") {
+                Response::status(404, "")
+            } else {
+                Response::ok(TIMEMAP)
+            }
+        });
+        let archive_today = ArchiveToday::with_api(
+            Client::new(),
+            &stand_in.url,
+            Duration::from_secs(30 * date::SECS_IN_DAY as u64),
+        );
+
+        let snapshot = archive_today
+            .find("https://example.com", date::parse_day("2021-01-15")?)
+            .await?
+            .unwrap();
+        assert_eq!(
+            "https://archive.ph/20210101000000/https://example.com",
+            snapshot.url
+        );
+        assert_eq!(ArchiveToday::NAME, snapshot.provider);
+
+        // the closest snapshot is too old
+        let snapshot = archive_today
+            .find("https://example.com", date::parse_day("2018-01-01")?)
+            .await?;
+        assert_eq!(None, snapshot);
+
+        // the closest snapshot was taken long after the submission
+        let snapshot = archive_today
+            .find("https://example.com", date::parse_day("2014-06-01")?)
+            .await?;
+        assert_eq!(None, snapshot);
+
+        let snapshot = archive_today
+            .find("https://example.com/missing", date::now())
+            .await?;
+        assert_eq!(None, snapshot);
+
+        assert_eq!("/timemap/https://example.com", stand_in.requests()[0].path);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+//! Archives throttle clients which send too many requests. A token bucket
+//! spreads our requests evenly while still allowing a few of them to run
+//! concurrently. Since the job must finish before it's killed, the limiter
+//! also has a time budget after which it refuses to hand out more tokens.
+
+use {
+    std::{error::Error, fmt, sync::Mutex, time::Duration},
+    tokio::time::{self, Instant},
+};
+
+/// Token bucket rate limiter shared by all requests to an archive.
+pub struct RateLimiter {
+    // how many tokens are added per second
+    rate: f64,
+    // how many tokens can accumulate, i.e. how many requests can be sent at
+    // once
+    burst: f64,
+    // no tokens are handed out after this instant
+    deadline: Option<Instant>,
+    state: Mutex<State>,
+}
+
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    // the archive asked us to back off until this instant
+    paused_until: Instant,
+}
+
+/// Returned when the time budget of the limiter is spent. Requests which
+/// didn't get a token should be deferred until the next run.
+#[derive(Debug)]
+pub struct BudgetExhausted;
+
+impl RateLimiter {
+    /// Creates a limiter with a full bucket. The `budget` starts running now.
+    pub fn new(rate: f64, burst: usize, budget: Option<Duration>) -> Self {
+        let burst = burst.max(1) as f64;
+        let now = Instant::now();
+
+        Self {
+            rate,
+            burst,
+            deadline: budget.map(|budget| now + budget),
+            state: Mutex::new(State {
+                tokens: burst,
+                refilled_at: now,
+                paused_until: now,
+            }),
+        }
+    }
+
+    /// Waits until a request can be sent. Fails without waiting if the token
+    /// wouldn't be available before the time budget is spent.
+    pub async fn acquire(&self) -> Result<(), BudgetExhausted> {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                let elapsed = now.duration_since(state.refilled_at);
+                state.tokens = (state.tokens
+                    + elapsed.as_secs_f64() * self.rate)
+                    .min(self.burst);
+                state.refilled_at = now;
+
+                if now < state.paused_until {
+                    state.paused_until - now
+                } else if state.tokens >= 1.0 {
+                    if matches!(self.deadline, Some(deadline) if now >= deadline)
+                    {
+                        return Err(BudgetExhausted);
+                    }
+                    state.tokens -= 1.0;
+                    return Ok(());
+                } else {
+                    Duration::from_secs_f64((1.0 - state.tokens) / self.rate)
+                }
+            };
+
+            if let Some(deadline) = self.deadline {
+                if Instant::now() + wait > deadline {
+                    return Err(BudgetExhausted);
+                }
+            }
+            time::sleep(wait).await;
+        }
+    }
+
+    /// How much of the time budget is left, `None` if there's no budget.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Stops handing out tokens for given duration, e.g. when the archive
+    /// responds with 429 and `Retry-After` header.
+    pub fn pause(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.paused_until = state.paused_until.max(Instant::now() + duration);
+        state.tokens = 0.0;
+    }
+}
+
+impl fmt::Display for BudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Time budget for archive requests is spent")
+    }
+}
+
+impl Error for BudgetExhausted {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_limits_rate() {
+        let limiter = RateLimiter::new(20.0, 2, None);
+        let started_at = Instant::now();
+
+        // the first two are a burst, the other two wait 50ms each
+        for _ in 0..4 {
+            limiter.acquire().await.unwrap();
+        }
+
+        let elapsed = started_at.elapsed();
+        assert!(elapsed >= Duration::from_millis(90), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn it_pauses() {
+        let limiter = RateLimiter::new(100.0, 1, None);
+        let started_at = Instant::now();
+
+        limiter.pause(Duration::from_millis(100));
+        limiter.acquire().await.unwrap();
+
+        assert!(started_at.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn it_respects_time_budget() {
+        let limiter =
+            RateLimiter::new(1.0, 1, Some(Duration::from_millis(100)));
+
+        limiter.acquire().await.unwrap();
+        // next token comes in a second which is past the deadline
+        let started_at = Instant::now();
+        assert!(limiter.acquire().await.is_err());
+        assert!(started_at.elapsed() < Duration::from_millis(50));
+
+        // the archive asks us to back off for longer than the budget
+        let limiter = RateLimiter::new(100.0, 1, Some(Duration::from_secs(1)));
+        limiter.pause(Duration::from_secs(10));
+        assert!(limiter.acquire().await.is_err());
+
+        // budget spent
+        let limiter =
+            RateLimiter::new(100.0, 1, Some(Duration::from_millis(10)));
+        time::sleep(Duration::from_millis(20)).await;
+        assert!(limiter.acquire().await.is_err());
+    }
+}
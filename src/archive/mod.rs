@@ -0,0 +1,252 @@
+//! Along with the article link, suckless.hn provides links to archived versions
+//! of the submission. Each archive is a [`Provider`]:
+//! * [`Wayback`] machine, which we also ask to capture urls it has no
+//!   snapshot of
+//! * [`ArchiveToday`], also known as archive.ph or archive.is
+//! * [`UrlTemplate`], a link built from a template given in the config, e.g.
+//!   to a self hosted archive
+//!
+//! A story has at most one link per provider and all of them are rendered.
+
+mod archive_today;
+mod limiter;
+mod url_template;
+mod wayback;
+
+pub use {
+    archive_today::ArchiveToday, url_template::UrlTemplate, wayback::Wayback,
+};
+
+use {
+    futures::stream::{self, StreamExt},
+    reqwest::Client,
+};
+
+use {
+    crate::{conf, date, prelude::*},
+    limiter::BudgetExhausted,
+};
+
+/// Archive which we look for snapshots of story urls in.
+pub enum Provider {
+    Wayback(Wayback),
+    ArchiveToday(ArchiveToday),
+    Template(UrlTemplate),
+}
+
+impl Provider {
+    /// Returns the providers enabled in the config. Wayback machine is always
+    /// enabled and shares the rate limit with given client.
+    pub fn from_conf(
+        wayback: &Wayback,
+        client: &Client,
+        conf: &conf::Conf,
+    ) -> Vec<Self> {
+        let mut providers = vec![Self::Wayback(wayback.clone())];
+
+        if conf.archive_today {
+            providers.push(Self::ArchiveToday(ArchiveToday::new(
+                client.clone(),
+                conf,
+            )));
+        }
+
+        providers.extend(conf.archive_templates.iter().map(
+            |(name, template)| Self::Template(UrlTemplate::new(name, template)),
+        ));
+
+        providers
+    }
+
+    /// Stored along with the link and displayed as the link label.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Wayback(_) => Wayback::NAME,
+            Self::ArchiveToday(_) => ArchiveToday::NAME,
+            Self::Template(template) => template.name(),
+        }
+    }
+
+    /// How many snapshots can be looked for at once. Archives without a rate
+    /// limiter are queried sequentially.
+    pub fn concurrency(&self) -> usize {
+        match self {
+            Self::Wayback(wayback) => wayback.concurrency(),
+            Self::ArchiveToday(_) | Self::Template(_) => 1,
+        }
+    }
+
+    /// Returns a link to the archived version of given url, preferably one
+    /// taken close to the submission time.
+    pub async fn find(
+        &self,
+        url: &str,
+        submitted_at: i64,
+    ) -> Result<Option<ArchiveLink>> {
+        match self {
+            Self::Wayback(wayback) => wayback.find(url, submitted_at).await,
+            Self::ArchiveToday(archive_today) => {
+                archive_today.find(url, submitted_at).await
+            }
+            Self::Template(template) => {
+                Ok(Some(template.render(url, submitted_at)))
+            }
+        }
+    }
+}
+
+/// Looks up archived versions of the story urls with each provider and assigns
+/// the links found to the stories. Returns ids of stories which some provider
+/// didn't get to within its time budget.
+pub async fn fetch_archives_for_stories(
+    providers: &[Provider],
+    stories: &mut [Story],
+) -> Vec<StoryId> {
+    // providers are independent services, therefore we query them
+    // concurrently
+    let jobs = providers
+        .iter()
+        .map(|provider| find_for_stories(provider, stories));
+    let results = futures::future::join_all(jobs).await;
+
+    let mut deferred = Vec::new();
+    for (index, result) in results.into_iter().flatten() {
+        match result {
+            Lookup::Found(archive) => stories[index].archives.push(archive),
+            Lookup::Deferred => deferred.push(stories[index].id),
+        }
+    }
+    deferred.sort_unstable();
+    deferred.dedup();
+
+    deferred
+}
+
+enum Lookup {
+    Found(ArchiveLink),
+    Deferred,
+}
+
+// Encodes all bytes but the unreserved characters as per RFC 3986.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'_'
+            | b'.'
+            | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// A snapshot taken long before or after the submission likely shows different
+// content than what the story linked to.
+fn is_close_to_submission(
+    archived_at: i64,
+    submitted_at: i64,
+    max_age: std::time::Duration,
+) -> bool {
+    (submitted_at - archived_at).abs() <= max_age.as_secs() as i64
+}
+
+// Returns outcomes of lookups by the provider along with the index of the
+// story they belong to. Stories without snapshot are omitted.
+async fn find_for_stories(
+    provider: &Provider,
+    stories: &[Story],
+) -> Vec<(usize, Lookup)> {
+    // only requests snapshots for urls, not for text
+    let urls = stories.iter().enumerate().filter_map(|(index, story)| {
+        match &story.kind {
+            StoryKind::Url(url) => {
+                let submitted_at = story.submitted_at.unwrap_or_else(date::now);
+                Some((index, url, submitted_at))
+            }
+            StoryKind::Text(_) => None,
+        }
+    });
+
+    // archive APIs are quick to throttle concurrent requests
+    stream::iter(urls)
+        .map(|(index, url, submitted_at)| async move {
+            match provider.find(url, submitted_at).await {
+                Ok(Some(archive)) => Some((index, Lookup::Found(archive))),
+                Ok(None) => None,
+                Err(e) if e.is::<BudgetExhausted>() => {
+                    Some((index, Lookup::Deferred))
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Cannot check {} snapshot for {}: {}",
+                        provider.name(),
+                        url,
+                        e
+                    );
+                    None
+                }
+            }
+        })
+        .buffered(provider.concurrency())
+        .filter_map(|lookup| async move { lookup })
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stand_in::{Response, StandIn},
+        std::time::Duration,
+    };
+
+    #[tokio::test]
+    async fn it_fetches_archives_from_all_providers() -> Result<()> {
+        let stand_in = StandIn::start(|req| {
+            if req.path.starts_with("/wayback/available") {
+                Response::ok(r#"{ "archived_snapshots": {} }"#)
+            } else {
+                Response::ok(
+                    "<https://archive.ph/20210101000000/https://example.com>; \
+                    rel=\"memento\"; datetime=\"Fri, 01 Jan 2021 00:00:00 GMT\"",
+                )
+            }
+        });
+        let client = Client::new();
+        let max_age = Duration::from_secs(30 * date::SECS_IN_DAY as u64);
+        let providers = [
+            Provider::Wayback(wayback::tests::test_wayback(&stand_in.url)),
+            Provider::ArchiveToday(ArchiveToday::with_api(
+                client,
+                &stand_in.url,
+                max_age,
+            )),
+            Provider::Template(UrlTemplate::new(
+                "ghost",
+                "https://ghostarchive.org/search?term={encoded_url}",
+            )),
+        ];
+
+        let mut story = Story::random_url();
+        story.kind = StoryKind::Url("https://example.com".to_string());
+        story.submitted_at = Some(date::parse_day("2021-01-02")?);
+        let mut stories = vec![story, Story::random_text()];
+
+        fetch_archives_for_stories(&providers, &mut stories).await;
+
+        let mut providers: Vec<_> = stories[0]
+            .archives
+            .iter()
+            .map(|archive| archive.provider.as_str())
+            .collect();
+        providers.sort_unstable();
+        assert_eq!(vec!["archive.today", "ghost"], providers);
+        assert!(stories[1].archives.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Archives which we don't query, e.g. a self hosted archive, are linked to via
+//! a template from the config. The template can contain placeholders:
+//! * `{url}` is the story url as is
+//! * `{encoded_url}` is the percent encoded story url
+//! * `{timestamp}` is the submission time as `YYYYMMDDhhmmss`, which many
+//!   archives accept to find the closest snapshot
+
+use super::percent_encode;
+use crate::{date, prelude::*};
+
+/// Builds archive links from a template.
+pub struct UrlTemplate {
+    name: String,
+    template: String,
+}
+
+impl UrlTemplate {
+    pub fn new(name: &str, template: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fills in the placeholders. We don't know whether the archive has the
+    /// snapshot, therefore the link has no archival time.
+    pub fn render(&self, url: &str, submitted_at: i64) -> ArchiveLink {
+        let url = self
+            .template
+            .replace("{encoded_url}", &percent_encode(url))
+            .replace("{timestamp}", &date::to_wayback_timestamp(submitted_at))
+            .replace("{url}", url);
+
+        ArchiveLink {
+            provider: self.name.clone(),
+            url,
+            archived_at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_renders_template() {
+        let template = UrlTemplate::new(
+            "mirror",
+            "https://mirror.example/{timestamp}/{url}?q={encoded_url}",
+        );
+
+        let archive = template.render("https://a.com/b?c=d e", 1379565972);
+
+        assert_eq!("mirror", archive.provider);
+        assert_eq!(
+            "https://mirror.example/20130919044612/https://a.com/b?c=d e\
+            ?q=https%3A%2F%2Fa.com%2Fb%3Fc%3Dd%20e",
+            archive.url
+        );
+        assert_eq!(None, archive.archived_at);
+    }
+}
@@ -0,0 +1,755 @@
+//! [Wayback machine][wayback] is the archive which we rely on the most.
+//!
+//! We ask for the snapshot closest to the time of the submission. A snapshot
+//! which was taken long before or after the story was submitted likely shows
+//! different content than what the story links to, such snapshots are ignored.
+//!
+//! If there's no snapshot, we ask Wayback machine to [save the page
+//! now][save-page-now]. Capturing a page takes a while, therefore we only
+//! remember that we asked and check whether the snapshot is available in the
+//! following runs.
+//!
+//! Wayback machine often has no snapshot of brand new links. Therefore, for a
+//! few days after insertion, we periodically check again for stories which
+//! have no archive link.
+//!
+//! All requests go through a [`RateLimiter`] which has a time budget per run.
+//! Stories which we didn't get to before the budget was spent are deferred to
+//! the following runs.
+//!
+//! [wayback]: https://web.archive.org
+//! [save-page-now]: https://web.archive.org/save
+
+use {
+    reqwest::{header, Client, Response, StatusCode},
+    rusqlite::Connection,
+    serde::Deserialize,
+    std::{sync::Arc, time::Duration},
+    tokio::time,
+};
+
+use super::{
+    limiter::{BudgetExhausted, RateLimiter},
+    percent_encode,
+};
+use crate::{conf, date, db, prelude::*};
+
+const WAYBACK_API: &str = "http://archive.org";
+const SAVE_PAGE_NOW_API: &str = "https://web.archive.org";
+
+// Capture usually takes up to a few minutes. After this many checks we assume
+// that wayback machine won't capture the url.
+const MAX_CAPTURE_CHECKS: i64 = 10;
+
+// Don't bother checking captures which were requested just now.
+const CAPTURE_DELAY: Duration = Duration::from_secs(60);
+
+// How long to wait before looking again for a missing snapshot of a story.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// How many times we send a request which was throttled.
+const MAX_ATTEMPTS: usize = 3;
+
+// How long to back off if a throttled response doesn't say.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(10);
+
+/// Wayback machine client.
+#[derive(Clone)]
+pub struct Wayback {
+    client: Client,
+    // base url of the availability API
+    api: String,
+    // base url of the save page now API
+    save_api: String,
+    // snapshots taken longer than this before or after the submission are
+    // ignored
+    max_age: Duration,
+    // whether to ask for capture of urls without snapshot
+    save_page_now: bool,
+    // how many urls at most we submit for capture in a single run
+    save_limit: usize,
+    // how long to wait between two capture requests
+    save_interval: Duration,
+    // how long after the request do we start checking for the snapshot
+    capture_delay: Duration,
+    // for how long after insertion do we look again for missing snapshots
+    recheck_window: Duration,
+    // how many stories at most we look again for in a single run
+    recheck_limit: usize,
+    // how long to wait before looking again for the same story
+    recheck_interval: Duration,
+    // shared by clones so that all requests in a run count towards the limit
+    limiter: Arc<RateLimiter>,
+    // how many snapshots can be looked for at once
+    concurrency: usize,
+}
+
+impl Wayback {
+    pub const NAME: &'static str = "wayback";
+
+    pub fn new(client: Client, conf: &conf::Conf) -> Self {
+        Self {
+            client,
+            api: WAYBACK_API.to_string(),
+            save_api: SAVE_PAGE_NOW_API.to_string(),
+            max_age: conf.archive_max_age,
+            save_page_now: conf.wayback_save_page_now,
+            save_limit: conf.wayback_save_limit,
+            save_interval: conf.wayback_save_interval,
+            capture_delay: CAPTURE_DELAY,
+            recheck_window: conf.archive_recheck_window,
+            recheck_limit: conf.archive_recheck_limit,
+            recheck_interval: RECHECK_INTERVAL,
+            limiter: Arc::new(RateLimiter::new(
+                conf.wayback_requests_per_sec,
+                conf.wayback_concurrency,
+                Some(conf.wayback_time_budget),
+            )),
+            concurrency: conf.wayback_concurrency.max(1),
+        }
+    }
+
+    /// How many snapshots can be looked for at once.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Asks wayback machine to capture urls of stories which don't have a
+    /// snapshot. Returns ids and urls of stories which were submitted. These
+    /// should be stored with [`db::insert_pending_captures`] and checked later
+    /// with [`Wayback::check_pending_captures`].
+    pub async fn save_missing_snapshots(
+        &self,
+        stories: &[Story],
+    ) -> Vec<(StoryId, String)> {
+        if !self.save_page_now {
+            return vec![];
+        }
+
+        let missing = stories
+            .iter()
+            .filter(|story| {
+                story.archives.iter().all(|a| a.provider != Self::NAME)
+            })
+            .filter_map(|story| match &story.kind {
+                StoryKind::Url(url) => Some((story.id, url)),
+                StoryKind::Text(_) => None,
+            })
+            .take(self.save_limit);
+
+        let mut requested = Vec::new();
+        for (i, (story_id, url)) in missing.enumerate() {
+            // the captures count towards the time budget, including the
+            // pauses between them
+            if i > 0 {
+                let remaining = self.limiter.remaining();
+                if matches!(remaining, Some(r) if r <= self.save_interval) {
+                    log::info!("Wayback time budget is spent, stopping.");
+                    break;
+                }
+                time::sleep(self.save_interval).await;
+            }
+
+            if self.limiter.acquire().await.is_err() {
+                log::info!("Wayback time budget is spent, stopping.");
+                break;
+            }
+
+            log::debug!("Requesting capture of {}", url);
+            // the url is a path segment, its query and fragment would
+            // otherwise be cut off
+            let mut req = self.client.get(format!(
+                "{}/save/{}",
+                self.save_api,
+                percent_encode(url)
+            ));
+            if let Some(remaining) = self.limiter.remaining() {
+                req = req.timeout(remaining);
+            }
+            let resp = req.send().await;
+
+            match resp {
+                Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    log::warn!("Save page now is throttling us, stopping.");
+                    self.limiter.pause(retry_after(&resp));
+                    break;
+                }
+                Ok(resp) if !resp.status().is_success() => {
+                    log::warn!(
+                        "Cannot request capture of {} (code {})",
+                        url,
+                        resp.status()
+                    );
+                }
+                // the capture might still be in progress, wayback machine
+                // doesn't respond until the page is archived
+                Err(e) if !e.is_timeout() => {
+                    log::warn!("Cannot request capture of {}: {}", url, e);
+                }
+                _ => requested.push((story_id, url.clone())),
+            }
+        }
+
+        requested
+    }
+
+    /// Checks whether urls which we asked to capture in previous runs have a
+    /// snapshot by now. If so, the stories are updated with the archive link.
+    pub async fn check_pending_captures(
+        &self,
+        conn: &Connection,
+        now: i64,
+    ) -> Result<()> {
+        let requested_before = now - self.capture_delay.as_secs() as i64;
+        let captures = db::select_pending_captures(
+            conn,
+            requested_before,
+            self.save_limit,
+        )?;
+
+        for capture in captures {
+            let snapshot =
+                match self.find(&capture.url, capture.requested_at).await {
+                    Ok(snapshot) => snapshot,
+                    Err(e) if e.is::<BudgetExhausted>() => {
+                        log::info!("{}, deferring capture checks.", e);
+                        break;
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Cannot check capture of {}: {}",
+                            capture.url,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+            if let Some(snapshot) = snapshot {
+                log::debug!("Capture of {} is available", capture.url);
+                db::update_archive(conn, capture.story_id, &snapshot)?;
+                db::delete_pending_capture(conn, capture.story_id)?;
+            } else if capture.checks + 1 >= MAX_CAPTURE_CHECKS {
+                log::debug!("Giving up on capture of {}", capture.url);
+                db::delete_pending_capture(conn, capture.story_id)?;
+            } else {
+                db::increment_capture_checks(conn, capture.story_id)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks again for snapshots of recently stored stories which didn't have
+    /// any at the time of insertion. Updates the stories which have one now.
+    pub async fn recheck_missing_snapshots(
+        &self,
+        conn: &Connection,
+        now: i64,
+    ) -> Result<()> {
+        let stories = db::select_stories_without_archive(
+            conn,
+            Self::NAME,
+            now - self.recheck_window.as_secs() as i64,
+            now - self.recheck_interval.as_secs() as i64,
+            self.recheck_limit,
+        )?;
+        log::debug!("Looking again for {} missing snapshots", stories.len());
+
+        for story in stories {
+            let url = match &story.kind {
+                StoryKind::Url(url) => url,
+                StoryKind::Text(_) => continue,
+            };
+            let submitted_at = story.submitted_at.unwrap_or(now);
+            let snapshot = match self.find(url, submitted_at).await {
+                Ok(snapshot) => snapshot,
+                // not marked as checked so that it comes first next time
+                Err(e) if e.is::<BudgetExhausted>() => {
+                    log::info!("{}, deferring snapshot rechecks.", e);
+                    break;
+                }
+                Err(e) => {
+                    log::warn!("Cannot check snapshot for {}: {}", url, e);
+                    None
+                }
+            };
+
+            db::update_archive_checked_at(conn, story.id)?;
+            if let Some(snapshot) = snapshot {
+                log::debug!("Found snapshot for story {}", story.id);
+                db::update_archive(conn, story.id, &snapshot)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if given url has a snapshot available which was taken close to
+    /// the submission time.
+    pub async fn find(
+        &self,
+        url: &str,
+        submitted_at: i64,
+    ) -> Result<Option<ArchiveLink>> {
+        // {
+        //     "archived_snapshots": {
+        //         "closest": { "url": "...", "timestamp": "20130919044612" }
+        //     }
+        // }
+        #[derive(Deserialize)]
+        struct WaybackResponse {
+            archived_snapshots: ClosestSnapshot,
+        }
+        #[derive(Deserialize)]
+        struct ClosestSnapshot {
+            closest: Option<Closest>,
+        }
+        #[derive(Deserialize)]
+        struct Closest {
+            url: String,
+            timestamp: String,
+        }
+
+        let timestamp = date::to_wayback_timestamp(submitted_at);
+        let mut attempt = 1;
+        let resp: WaybackResponse = loop {
+            self.limiter.acquire().await?;
+
+            let resp = self
+                .client
+                .get(format!("{}/wayback/available", self.api))
+                .query(&[("url", url), ("timestamp", &timestamp)])
+                .send()
+                .await?;
+
+            if resp.status() == StatusCode::TOO_MANY_REQUESTS
+                && attempt < MAX_ATTEMPTS
+            {
+                log::debug!("Wayback is throttling us, backing off.");
+                self.limiter.pause(retry_after(&resp));
+                attempt += 1;
+                continue;
+            }
+
+            break resp.error_for_status()?.json().await?;
+        };
+
+        let closest = match resp.archived_snapshots.closest {
+            Some(closest) => closest,
+            None => return Ok(None),
+        };
+
+        let archived_at = date::parse_wayback_timestamp(&closest.timestamp)?;
+        if !super::is_close_to_submission(
+            archived_at,
+            submitted_at,
+            self.max_age,
+        ) {
+            log::debug!(
+                "Ignoring snapshot {} of {} taken too far from the submission",
+                closest.url,
+                url
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(ArchiveLink {
+            provider: Self::NAME.to_string(),
+            url: closest.url,
+            archived_at: Some(archived_at),
+        }))
+    }
+}
+
+// Reads how long to back off from the `Retry-After` header. We only support
+// the delay in seconds, not the http date.
+fn retry_after(resp: &Response) -> Duration {
+    resp.headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+}
+
+#[cfg(test)]
+pub mod tests {
+    //! These sets must be ran sequentially, hence they're in a single test
+    //! case. Not running them sequentially causes some weird failures.
+
+    use {
+        super::*,
+        crate::{
+            archive::{fetch_archives_for_stories, Provider},
+            date::SECS_IN_DAY,
+            stand_in::{Response, StandIn},
+        },
+    };
+
+    const MONTH: Duration = Duration::from_secs(30 * SECS_IN_DAY as u64);
+
+    pub fn test_wayback(api: &str) -> Wayback {
+        Wayback {
+            client: Client::new(),
+            api: api.to_string(),
+            save_api: api.to_string(),
+            max_age: MONTH,
+            save_page_now: true,
+            save_limit: 2,
+            save_interval: Duration::from_millis(10),
+            capture_delay: Duration::from_secs(0),
+            recheck_window: MONTH,
+            recheck_limit: 2,
+            recheck_interval: Duration::from_secs(0),
+            limiter: Arc::new(RateLimiter::new(1000.0, 1, None)),
+            concurrency: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn it_fetches_snapshots() -> Result<()> {
+        env_logger::init();
+        let wayback = test_wayback(WAYBACK_API);
+        let now = date::now();
+
+        // single existing snapshot
+        let snapshot = wayback.find("https://porkbrain.com", now).await?;
+        assert_ne!(None, snapshot, "Expected snapshot");
+
+        // single non existing snapshot
+        let snapshot = wayback
+            .find("https://porkbrain.com/non-existent", now)
+            .await?;
+        assert_eq!(None, snapshot, "Didn't expect snapshot");
+
+        // multiple snapshots
+        let mut porkbrain = Story::random_url();
+        porkbrain.kind = StoryKind::Url("https://porkbrain.com".to_string());
+        let mut stories =
+            vec![porkbrain, Story::random_url(), Story::random_text()];
+
+        fetch_archives_for_stories(&[Provider::Wayback(wayback)], &mut stories)
+            .await;
+
+        assert_eq!(1, stories[0].archives.len());
+        assert!(stories[1].archives.is_empty());
+        assert!(stories[2].archives.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_ignores_snapshots_far_from_submission() -> Result<()> {
+        let stand_in = StandIn::start(|req| {
+            let body = if req.path.contains("fresh") {
+                r#"{ "archived_snapshots": { "closest": {
+                    "url": "http://web.archive.org/web/20210101000000/fresh",
+                    "timestamp": "20210101000000"
+                } } }"#
+            } else if req.path.contains("later") {
+                r#"{ "archived_snapshots": { "closest": {
+                    "url": "http://web.archive.org/web/20230101000000/later",
+                    "timestamp": "20230101000000"
+                } } }"#
+            } else {
+                r#"{ "archived_snapshots": { "closest": {
+                    "url": "http://web.archive.org/web/20150101000000/stale",
+                    "timestamp": "20150101000000"
+                } } }"#
+            };
+            Response::ok(body)
+        });
+        let wayback = test_wayback(&stand_in.url);
+
+        let mut fresh = Story::random_url();
+        fresh.kind = StoryKind::Url("https://example.com/fresh".to_string());
+        fresh.submitted_at = Some(date::parse_day("2021-01-15")?);
+        let mut stale = Story::random_url();
+        stale.kind = StoryKind::Url("https://example.com/stale".to_string());
+        stale.submitted_at = Some(date::parse_day("2021-01-15")?);
+        // e.g. backfilled stories of which the only snapshot is recent
+        let mut later = Story::random_url();
+        later.kind = StoryKind::Url("https://example.com/later".to_string());
+        later.submitted_at = Some(date::parse_day("2021-01-15")?);
+        let mut stories = vec![fresh, stale, later];
+
+        fetch_archives_for_stories(&[Provider::Wayback(wayback)], &mut stories)
+            .await;
+
+        assert_eq!(
+            vec![ArchiveLink {
+                provider: Wayback::NAME.to_string(),
+                url: "http://web.archive.org/web/20210101000000/fresh"
+                    .to_string(),
+                archived_at: Some(date::parse_day("2021-01-01")?),
+            }],
+            stories[0].archives
+        );
+        assert!(stories[1].archives.is_empty());
+        assert!(stories[2].archives.is_empty());
+
+        // asks for the snapshot closest to the submission
+        let requests = stand_in.requests();
+        assert!(requests[0].path.contains("timestamp=20210115000000"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_saves_missing_snapshots() -> Result<()> {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        // the snapshot becomes available once the capture is requested
+        let captured = Arc::new(AtomicBool::new(false));
+        let captured_by_stand_in = Arc::clone(&captured);
+        let stand_in = StandIn::start(move |req| {
+            if req
+                .path
+                .starts_with("/save/https%3A%2F%2Fexample.com%2Fthr")
+            {
+                Response::status(429, "")
+            } else if req.path.starts_with("/save/") {
+                captured_by_stand_in.store(true, Ordering::SeqCst);
+                Response::ok("")
+            } else if captured_by_stand_in.load(Ordering::SeqCst) {
+                Response::ok(format!(
+                    r#"{{ "archived_snapshots": {{ "closest": {{
+                        "url": "http://web.archive.org/web/{}/new",
+                        "timestamp": "{}"
+                    }} }} }}"#,
+                    date::to_wayback_timestamp(date::now()),
+                    date::to_wayback_timestamp(date::now()),
+                ))
+            } else {
+                Response::ok(r#"{ "archived_snapshots": {} }"#)
+            }
+        });
+        let mut wayback = test_wayback(&stand_in.url);
+        let conn = db::tests::test_conn()?;
+
+        let mut new = Story::random_url();
+        new.kind = StoryKind::Url("https://example.com/new?id=1#c".to_string());
+        let mut throttled = Story::random_url();
+        throttled.kind =
+            StoryKind::Url("https://example.com/throttled".to_string());
+        let mut stories = vec![new, throttled, Story::random_text()];
+        let new_id = stories[0].id;
+
+        let providers = [Provider::Wayback(wayback.clone())];
+        fetch_archives_for_stories(&providers, &mut stories).await;
+        assert!(stories.iter().all(|story| story.archives.is_empty()));
+        let test_data: Vec<_> = stories
+            .iter()
+            .map(|story| (story.clone(), vec![]))
+            .collect();
+        db::tests::insert_test_data(&conn, &test_data)?;
+
+        let requested = wayback.save_missing_snapshots(&stories).await;
+        db::insert_pending_captures(&conn, &requested)?;
+        let pending = db::select_pending_captures(&conn, i64::MAX, 10)?;
+        assert_eq!(1, pending.len());
+        assert_eq!(new_id, pending[0].story_id);
+        assert!(stand_in.requests().iter().any(|req| req.path
+            == "/save/https%3A%2F%2Fexample.com%2Fnew%3Fid%3D1%23c"));
+
+        // captures requested just now are not checked yet
+        wayback.capture_delay = Duration::from_secs(60);
+        wayback.check_pending_captures(&conn, date::now()).await?;
+        assert!(db::select_archives(&conn, new_id)?.is_empty());
+
+        wayback.capture_delay = Duration::from_secs(0);
+        // requested_at has a resolution of seconds
+        wayback
+            .check_pending_captures(&conn, date::now() + 1)
+            .await?;
+
+        let archives = db::select_archives(&conn, new_id)?;
+        assert_eq!(1, archives.len());
+        assert!(archives[0].url.starts_with("http://web.archive.org/web/"));
+        assert!(db::select_pending_captures(&conn, i64::MAX, 10)?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_counts_captures_towards_budget() -> Result<()> {
+        let stand_in = StandIn::start(|_| Response::ok(""));
+        let mut wayback = test_wayback(&stand_in.url);
+        wayback.save_interval = Duration::from_millis(200);
+        wayback.limiter = Arc::new(RateLimiter::new(
+            1000.0,
+            1,
+            Some(Duration::from_millis(100)),
+        ));
+
+        let stories = vec![Story::random_url(), Story::random_url()];
+        let started_at = time::Instant::now();
+        let requested = wayback.save_missing_snapshots(&stories).await;
+
+        // there's no time left to wait for the second capture
+        assert_eq!(1, requested.len());
+        assert_eq!(1, stand_in.requests().len());
+        assert!(started_at.elapsed() < Duration::from_millis(200));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_retries_throttled_requests() -> Result<()> {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        let throttled = Arc::new(AtomicBool::new(false));
+        let throttled_by_stand_in = Arc::clone(&throttled);
+        let stand_in = StandIn::start(move |_| {
+            if throttled_by_stand_in.swap(true, Ordering::SeqCst) {
+                Response::ok(r#"{ "archived_snapshots": {} }"#)
+            } else {
+                Response::status(429, "").header("Retry-After", "1")
+            }
+        });
+        let wayback = test_wayback(&stand_in.url);
+
+        let started_at = time::Instant::now();
+        let snapshot = wayback.find("https://example.com", date::now()).await?;
+
+        assert_eq!(None, snapshot);
+        assert_eq!(2, stand_in.requests().len());
+        assert!(started_at.elapsed() >= Duration::from_secs(1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_defers_lookups_over_budget() -> Result<()> {
+        let stand_in =
+            StandIn::start(|_| Response::ok(r#"{ "archived_snapshots": {} }"#));
+        let mut wayback = test_wayback(&stand_in.url);
+        // a token per second, the first one is available right away
+        wayback.limiter = Arc::new(RateLimiter::new(
+            1.0,
+            1,
+            Some(Duration::from_millis(500)),
+        ));
+
+        let mut stories = vec![
+            Story::random_url(),
+            Story::random_url(),
+            Story::random_text(),
+        ];
+        let deferred = fetch_archives_for_stories(
+            &[Provider::Wayback(wayback)],
+            &mut stories,
+        )
+        .await;
+
+        assert_eq!(vec![stories[1].id], deferred);
+        assert_eq!(1, stand_in.requests().len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_gives_up_on_capture() -> Result<()> {
+        let stand_in =
+            StandIn::start(|_| Response::ok(r#"{ "archived_snapshots": {} }"#));
+        let wayback = test_wayback(&stand_in.url);
+        let conn = db::tests::test_conn()?;
+
+        let story = Story::random_url();
+        let story_id = story.id;
+        db::insert_stories(&conn, vec![story])?;
+        db::insert_pending_captures(
+            &conn,
+            &[(story_id, "https://example.com".to_string())],
+        )?;
+        // requested_at has a resolution of seconds
+        let now = date::now() + 1;
+
+        for checks in 1..MAX_CAPTURE_CHECKS {
+            wayback.check_pending_captures(&conn, now).await?;
+            let pending = db::select_pending_captures(&conn, i64::MAX, 10)?;
+            assert_eq!(checks, pending[0].checks);
+        }
+        wayback.check_pending_captures(&conn, now).await?;
+        assert!(db::select_pending_captures(&conn, i64::MAX, 10)?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_rechecks_missing_snapshots() -> Result<()> {
+        let stand_in = StandIn::start(|req| {
+            if req.path.contains("found") {
+                Response::ok(format!(
+                    r#"{{ "archived_snapshots": {{ "closest": {{
+                        "url": "http://web.archive.org/web/found",
+                        "timestamp": "{}"
+                    }} }} }}"#,
+                    date::to_wayback_timestamp(date::now()),
+                ))
+            } else {
+                Response::ok(r#"{ "archived_snapshots": {} }"#)
+            }
+        });
+        let wayback = test_wayback(&stand_in.url);
+        let conn = db::tests::test_conn()?;
+
+        let url_story = |url: &str| {
+            let mut story = Story::random_url();
+            story.kind = StoryKind::Url(url.to_string());
+            (story, vec![])
+        };
+        let mut archived = url_story("https://example.com/archived");
+        archived.0.archives = vec![ArchiveLink {
+            provider: Wayback::NAME.to_string(),
+            url: "http://web.archive.org/web/a".to_string(),
+            archived_at: Some(date::now()),
+        }];
+        let stories = vec![
+            url_story("https://example.com/found"),
+            url_story("https://example.com/missing"),
+            url_story("https://example.com/pending"),
+            archived,
+            (Story::random_text(), vec![]),
+        ];
+        db::tests::insert_test_data(&conn, &stories)?;
+        db::insert_pending_captures(
+            &conn,
+            &[(stories[2].0.id, "https://example.com/pending".to_string())],
+        )?;
+
+        // stories were checked at insertion, which has a resolution of
+        // seconds
+        wayback
+            .recheck_missing_snapshots(&conn, date::now() + 1)
+            .await?;
+
+        // only the stories without archive link, without pending capture and
+        // with url are checked
+        let requests = stand_in.requests();
+        assert_eq!(2, requests.len());
+        assert!(requests.iter().any(|r| r.path.contains("found")));
+        assert!(requests.iter().any(|r| r.path.contains("missing")));
+
+        let found = db::select_archives(&conn, stories[0].0.id)?;
+        assert_eq!("http://web.archive.org/web/found", found[0].url);
+        assert!(db::select_archives(&conn, stories[1].0.id)?.is_empty());
+
+        // the story which is still missing is checked again
+        wayback
+            .recheck_missing_snapshots(&conn, date::now() + 2)
+            .await?;
+        let requests = stand_in.requests();
+        assert_eq!(3, requests.len());
+        assert!(requests[2].path.contains("missing"));
+
+        Ok(())
+    }
+}
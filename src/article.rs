@@ -0,0 +1,318 @@
+//! Filters only see the title and the url of a story, which says little about
+//! the content. If enabled, we fetch the linked page of each new story and
+//! extract what we can about the article: its title, meta description,
+//! declared language, word count and readable text.
+//!
+//! We don't parse the html properly. A handful of regexes is good enough for
+//! the few things we look for and unlike a parser they don't mind broken html.
+
+use {
+    futures::stream::{self, StreamExt},
+    lazy_static::lazy_static,
+    regex::Regex,
+    reqwest::{header, Client},
+    std::time::Duration,
+};
+
+use crate::{filter::paywall, prelude::*};
+
+// How many pages we fetch at once.
+const CONCURRENCY: usize = 8;
+
+// We store only the beginning of the readable text, it's enough to tell the
+// language and to search in, but doesn't bloat the database.
+const MAX_TEXT_CHARS: usize = 20_000;
+
+lazy_static! {
+    static ref TITLE: Regex =
+        Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    static ref META: Regex = Regex::new(r"(?is)<meta\b[^>]*>").unwrap();
+    static ref ATTRIBUTE: Regex =
+        Regex::new(r#"(?is)([a-z][a-z0-9:_-]*)\s*=\s*(?:"([^"]*)"|'([^']*)')"#)
+            .unwrap();
+    static ref HTML_LANG: Regex =
+        Regex::new(r#"(?is)<html\b[^>]*?\blang\s*=\s*["']?([a-z]{2,3})\b"#)
+            .unwrap();
+    // content of these elements is not readable text
+    static ref NOT_TEXT: Regex = Regex::new(concat!(
+        r"(?is)<!--.*?-->",
+        r"|<script\b.*?</script\s*>",
+        r"|<style\b.*?</style\s*>",
+        r"|<noscript\b.*?</noscript\s*>",
+        r"|<svg\b.*?</svg\s*>",
+        r"|<template\b.*?</template\s*>",
+        r"|<title\b.*?</title\s*>",
+    ))
+    .unwrap();
+    static ref TAG: Regex = Regex::new(r"(?s)<[^>]*>").unwrap();
+    static ref ENTITY: Regex =
+        Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]+);").unwrap();
+}
+
+/// Fetches the linked page of each url story and attaches what we extracted
+/// from it. Only the first `max_bytes` of a page are read and each page must
+/// be fetched within the `timeout`. Pages which cannot be fetched or aren't
+/// html are skipped.
+pub async fn fetch_for_stories(
+    client: &Client,
+    stories: &mut [Story],
+    max_bytes: usize,
+    timeout: Duration,
+) {
+    let urls = stories
+        .iter()
+        .enumerate()
+        .filter_map(|(index, story)| match &story.kind {
+            StoryKind::Url(url) => Some((index, url.clone())),
+            StoryKind::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let articles: Vec<_> = stream::iter(urls)
+        .map(|(index, url)| async move {
+            match fetch_html(client, &url, max_bytes, timeout).await {
+                Ok(Some(html)) => Some((index, extract(&html))),
+                Ok(None) => None,
+                Err(e) => {
+                    log::debug!("Cannot fetch article {}: {}", url, e);
+                    None
+                }
+            }
+        })
+        .buffer_unordered(CONCURRENCY)
+        .filter_map(|article| async move { article })
+        .collect()
+        .await;
+
+    for (index, article) in articles {
+        stories[index].article = Some(article);
+    }
+}
+
+/// Extracts the article information from the html of the page.
+pub fn extract(html: &str) -> Article {
+    let title = TITLE
+        .captures(html)
+        .map(|captures| clean(&captures[1]))
+        .filter(|title| !title.is_empty());
+
+    let description = meta_description(html);
+
+    let lang = HTML_LANG
+        .captures(html)
+        .map(|captures| captures[1].to_lowercase());
+
+    let text = clean(&TAG.replace_all(&NOT_TEXT.replace_all(html, " "), " "));
+    let word_count = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+    let text = match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    };
+
+    Article {
+        title,
+        description,
+        lang,
+        word_count,
+        text,
+        paywall_marker: paywall::find_marker(html),
+    }
+}
+
+// Returns None if the page isn't html. Reads at most `max_bytes` of the body,
+// a truncated page is still good enough for us.
+async fn fetch_html(
+    client: &Client,
+    url: &str,
+    max_bytes: usize,
+    timeout: Duration,
+) -> Result<Option<String>> {
+    let mut resp = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    let is_html = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_lowercase().contains("html"))
+        // some servers don't bother
+        .unwrap_or(true);
+    if !is_html {
+        return Ok(None);
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= max_bytes {
+            body.truncate(max_bytes);
+            break;
+        }
+    }
+
+    // truncation might have split a multibyte character
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+// The "description" meta tag, or the Open Graph one as a fallback.
+fn meta_description(html: &str) -> Option<String> {
+    let mut description = None;
+    for tag in META.find_iter(html) {
+        let mut name = None;
+        let mut content = None;
+        for captures in ATTRIBUTE.captures_iter(tag.as_str()) {
+            let value = captures.get(2).or_else(|| captures.get(3));
+            let value = value.map(|value| value.as_str()).unwrap_or_default();
+            match captures[1].to_lowercase().as_str() {
+                "name" | "property" => name = Some(value.to_lowercase()),
+                "content" => content = Some(clean(value)),
+                _ => (),
+            }
+        }
+
+        match (name.as_deref(), content) {
+            (Some("description"), Some(content)) if !content.is_empty() => {
+                return Some(content)
+            }
+            (Some("og:description"), Some(content)) if !content.is_empty() => {
+                description = Some(content)
+            }
+            _ => (),
+        }
+    }
+
+    description
+}
+
+// Decodes html entities and collapses whitespace.
+fn clean(text: &str) -> String {
+    let decoded = ENTITY.replace_all(text, |captures: &regex::Captures| {
+        let entity = &captures[1];
+        let decoded = if let Some(hex) = entity.strip_prefix("#x") {
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        } else if let Some(hex) = entity.strip_prefix("#X") {
+            u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+        } else if let Some(decimal) = entity.strip_prefix('#') {
+            decimal.parse().ok().and_then(char::from_u32)
+        } else {
+            match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "ndash" => Some('–'),
+                "mdash" => Some('—'),
+                "hellip" => Some('…'),
+                _ => None,
+            }
+        };
+
+        decoded
+            .map(String::from)
+            .unwrap_or_else(|| captures[0].to_string())
+    });
+
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stand_in::{Response, StandIn},
+    };
+
+    #[test]
+    fn it_extracts_article() {
+        let html = r#"<!DOCTYPE html>
+            <html class="no-js" lang="de-AT">
+            <head>
+                <title>
+                    Rust &amp; Go &#8211; a comparison
+                </title>
+                <meta property="og:description" content="Open Graph">
+                <meta content='Two languages,
+                    compared.' name='Description'>
+                <style>body { color: red; }</style>
+                <script>var words = "not counted at all";</script>
+            </head>
+            <body>
+                <!-- nor this comment -->
+                <h1>Rust &amp; Go</h1>
+                <p>Both are <em>fast</em> &mdash; really.</p>
+            </body>
+            </html>"#;
+
+        let article = extract(html);
+        assert_eq!(Some("Rust & Go – a comparison"), article.title.as_deref());
+        assert_eq!(
+            Some("Two languages, compared."),
+            article.description.as_deref()
+        );
+        assert_eq!(Some("de"), article.lang.as_deref());
+        assert_eq!("Rust & Go Both are fast — really.", article.text);
+        // the ampersand and the dash aren't words
+        assert_eq!(6, article.word_count);
+
+        let article = extract(
+            r#"<meta property="og:description" content="Fallback"><p>Hi</p>"#,
+        );
+        assert_eq!(None, article.title);
+        assert_eq!(Some("Fallback"), article.description.as_deref());
+        assert_eq!(None, article.lang);
+        assert_eq!(1, article.word_count);
+    }
+
+    #[tokio::test]
+    async fn it_fetches_articles() {
+        let long_page = format!("<p>{}</p>", "word ".repeat(10_000));
+        let stand_in = StandIn::start(move |req| match req.path.as_str() {
+            "/article" => Response::ok(
+                "<html lang=en><title>Article</title><p>Read me</p></html>",
+            )
+            .header("Content-Type", "text/html; charset=utf-8"),
+            "/long" => Response::ok(long_page.clone()),
+            "/paper.pdf" => Response::ok("%PDF-1.4")
+                .header("Content-Type", "application/pdf"),
+            _ => Response::status(404, "<title>Not found</title>"),
+        });
+
+        let mut stories: Vec<_> = ["/article", "/long", "/paper.pdf", "/gone"]
+            .iter()
+            .map(|path| {
+                let mut story = Story::random_url();
+                story.kind =
+                    StoryKind::Url(format!("{}{}", stand_in.url, path));
+                story
+            })
+            .collect();
+        stories.push(Story::random_text());
+
+        let timeout = Duration::from_secs(5);
+        fetch_for_stories(&Client::new(), &mut stories, 1000, timeout).await;
+
+        let article = stories[0].article.as_ref().unwrap();
+        assert_eq!(Some("Article"), article.title.as_deref());
+        assert_eq!(Some("en"), article.lang.as_deref());
+        assert_eq!(2, article.word_count);
+
+        // only the first 1000 bytes are read
+        let article = stories[1].article.as_ref().unwrap();
+        assert_eq!(200, article.word_count);
+
+        assert!(stories[2].article.is_none());
+        assert!(stories[3].article.is_none());
+        assert!(stories[4].article.is_none());
+        // text stories aren't fetched
+        assert_eq!(4, stand_in.requests().len());
+    }
+}
@@ -0,0 +1,117 @@
+//! The same article is often submitted several times with a slightly different
+//! url: with tracking params, with or without `www.`, over http or https, with
+//! a trailing slash and so on. A canonical url strips these differences so that
+//! we can tell that two stories link to the same content.
+//!
+//! The canonical url is only used for comparison, it's not a valid url as it
+//! has no scheme.
+
+use reqwest::Url;
+
+// Query params of known trackers which only tell the site where the visitor
+// came from. Besides these, all params starting with "utm_" are dropped.
+// Generic names such as "ref" or "share" are kept as some sites use them as
+// content ids.
+const TRACKING_PARAMS: &[&str] = &[
+    "_ga", "dclid", "fbclid", "gclid", "igshid", "mc_cid", "mc_eid", "msclkid",
+    "ref_src", "smid",
+];
+
+/// Returns canonical form of the url. Urls which are not http(s) are returned
+/// trimmed but otherwise as they are.
+pub fn url(raw: &str) -> String {
+    let raw = raw.trim();
+    let parsed = match Url::parse(raw) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => return raw.to_string(),
+    };
+
+    // lowercased by the parser
+    let host = parsed.host_str().unwrap_or_default();
+    let mut canonical = host.strip_prefix("www.").unwrap_or(host).to_string();
+
+    // the parser already omits the default port of the scheme
+    if let Some(port) = parsed.port() {
+        canonical.push_str(&format!(":{}", port));
+    }
+
+    canonical.push_str(parsed.path().trim_end_matches('/'));
+
+    // params are kept encoded, the order doesn't matter
+    let mut params: Vec<_> = parsed
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default();
+            !name.is_empty()
+                && !name.starts_with("utm_")
+                && !TRACKING_PARAMS.contains(&name)
+        })
+        .collect();
+    if !params.is_empty() {
+        params.sort_unstable();
+        canonical.push('?');
+        canonical.push_str(&params.join("&"));
+    }
+
+    // fragment is dropped
+
+    canonical
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_canonicalizes_urls() {
+        let same = [
+            "https://example.com/article",
+            "http://example.com/article",
+            "https://www.example.com/article/",
+            "https://EXAMPLE.com/article#comments",
+            "https://example.com:443/article",
+            "http://example.com:80/article",
+            "https://example.com/article?utm_source=hn&utm_medium=social",
+            "https://example.com/article?ref_src=twsrc",
+            "  https://example.com/article?fbclid=abc  ",
+        ];
+        for raw in &same {
+            assert_eq!("example.com/article", url(raw), "{}", raw);
+        }
+
+        assert_eq!(
+            "example.com/article?a=1&b=2",
+            url("https://example.com/article?b=2&utm_campaign=x&a=1")
+        );
+        assert_eq!("example.com", url("https://www.example.com/"));
+        // generic names might identify the content
+        assert_eq!(
+            "example.com/post?ref=123&share=abc",
+            url("https://example.com/post?share=abc&ref=123")
+        );
+        assert_eq!(
+            "example.com:8080/article",
+            url("http://example.com:8080/article")
+        );
+        assert_eq!(
+            "news.ycombinator.com/item?id=1",
+            url("https://news.ycombinator.com/item?id=1")
+        );
+
+        // path is case sensitive
+        assert_ne!(
+            url("https://example.com/Article"),
+            url("https://example.com/article")
+        );
+        // different subdomains are different sites
+        assert_ne!(
+            url("https://blog.example.com/article"),
+            url("https://example.com/article")
+        );
+
+        assert_eq!("not a url", url("not a url"));
+        assert_eq!("ftp://example.com/", url("ftp://example.com/"));
+    }
+}
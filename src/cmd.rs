@@ -0,0 +1,247 @@
+//! Besides the periodic run, the binary supports a few maintenance commands.
+//! The first argument selects the command, running without arguments
+//! generates the pages.
+
+use crate::{date, filter, prelude::*};
+
+pub const USAGE: &str = "\
+Usage:
+    sucklesshn
+        Fetches new top stories and generates pages.
+    sucklesshn import-algolia <FROM YYYY-MM-DD> <TO YYYY-MM-DD>
+        Imports front page stories submitted within the range of days (TO is
+        exclusive) from Algolia HN search.
+    sucklesshn evaluate-filter <FILTER> [KEYWORD...]
+        Runs the current version of the filter, or a filter matching any of
+        the keywords if given, over all stored stories and lists the stories
+        whose flag would change.
+    sucklesshn label <TOPIC> <on|off> <STORY ID>...
+        Labels stored stories as on or off the topic. Topics are aihype, crypto
+        and politics.
+    sucklesshn train-topic <TOPIC>
+        Trains the classifier of the topic from the labelled stories, saves it
+        to TOPIC_MODELS_DIR and lists the most telling tokens.
+    sucklesshn explain-topic <TOPIC> <STORY ID>
+        Shows how each token of a stored story contributes to the decision of
+        the trained classifier of the topic.
+    sucklesshn stats [--json]
+        Prints how many stories each filter flagged within the last STATS_DAYS
+        days, by day and by week, how often filters flag the same stories and
+        the top domains and reasons behind the flags.
+    sucklesshn search <QUERY>...
+        Lists stored stories whose title or fetched article text contains all
+        the words. A word ending with * matches any word with that prefix.";
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run,
+    /// Unix times, `to` is exclusive.
+    ImportAlgolia {
+        from: i64,
+        to: i64,
+    },
+    /// Keywords, if any, replace the filter logic.
+    EvaluateFilter {
+        filter: FilterKind,
+        keywords: Vec<String>,
+    },
+    LabelTopic {
+        topic: FilterKind,
+        on_topic: bool,
+        stories: Vec<StoryId>,
+    },
+    TrainTopic {
+        topic: FilterKind,
+    },
+    ExplainTopic {
+        topic: FilterKind,
+        story: StoryId,
+    },
+    Stats {
+        json: bool,
+    },
+    Search {
+        query: String,
+    },
+}
+
+impl Command {
+    /// Parses command from the program arguments, excluding the program name.
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let args: Vec<_> = args.iter().map(|arg| arg.as_str()).collect();
+
+        match args.as_slice() {
+            [] => Ok(Self::Run),
+            ["import-algolia", from, to] => {
+                let from = date::parse_day(from)?;
+                let to = date::parse_day(to)?;
+                if from >= to {
+                    return Err("FROM must be before TO".into());
+                }
+
+                Ok(Self::ImportAlgolia { from, to })
+            }
+            ["evaluate-filter", name, keywords @ ..] => {
+                let filter = filter::by_name(name)
+                    .ok_or_else(|| format!("Unknown filter '{}'", name))?;
+                let keywords = keywords
+                    .iter()
+                    .map(|keyword| keyword.to_string())
+                    .collect();
+
+                Ok(Self::EvaluateFilter { filter, keywords })
+            }
+            ["label", topic, label, stories @ ..] if !stories.is_empty() => {
+                let topic = parse_topic(topic)?;
+                let on_topic = match *label {
+                    "on" => true,
+                    "off" => false,
+                    _ => return Err("Label must be 'on' or 'off'".into()),
+                };
+                let stories = stories
+                    .iter()
+                    .map(|story| parse_story_id(story))
+                    .collect::<Result<_>>()?;
+
+                Ok(Self::LabelTopic {
+                    topic,
+                    on_topic,
+                    stories,
+                })
+            }
+            ["train-topic", topic] => Ok(Self::TrainTopic {
+                topic: parse_topic(topic)?,
+            }),
+            ["explain-topic", topic, story] => Ok(Self::ExplainTopic {
+                topic: parse_topic(topic)?,
+                story: parse_story_id(story)?,
+            }),
+            ["stats"] => Ok(Self::Stats { json: false }),
+            ["stats", "--json"] => Ok(Self::Stats { json: true }),
+            ["search", words @ ..] if !words.is_empty() => Ok(Self::Search {
+                query: words.join(" "),
+            }),
+            _ => Err(USAGE.into()),
+        }
+    }
+}
+
+fn parse_topic(name: &str) -> Result<FilterKind> {
+    filter::topic::by_name(name)
+        .ok_or_else(|| format!("Unknown topic '{}'", name).into())
+}
+
+fn parse_story_id(id: &str) -> Result<StoryId> {
+    id.parse()
+        .map_err(|_| format!("Invalid story id '{}'", id).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn it_parses_commands() -> Result<()> {
+        assert_eq!(Command::Run, Command::from_args(&[])?);
+        assert_eq!(
+            Command::ImportAlgolia {
+                from: 1609459200,
+                to: 1609545600
+            },
+            Command::from_args(&args(&[
+                "import-algolia",
+                "2021-01-01",
+                "2021-01-02"
+            ]))?
+        );
+
+        assert!(Command::from_args(&args(&["import-algolia"])).is_err());
+        assert!(Command::from_args(&args(&[
+            "import-algolia",
+            "2021-01-02",
+            "2021-01-01"
+        ]))
+        .is_err());
+        assert_eq!(
+            Command::EvaluateFilter {
+                filter: FilterKind::BigTech,
+                keywords: vec![]
+            },
+            Command::from_args(&args(&["evaluate-filter", "amfg"]))?
+        );
+        assert_eq!(
+            Command::EvaluateFilter {
+                filter: FilterKind::AskHn,
+                keywords: vec!["ask".to_string(), "tell".to_string()]
+            },
+            Command::from_args(&args(&[
+                "evaluate-filter",
+                "askhn",
+                "ask",
+                "tell"
+            ]))?
+        );
+        assert!(Command::from_args(&args(&["evaluate-filter"])).is_err());
+        assert!(Command::from_args(&args(&["evaluate-filter", "x"])).is_err());
+
+        assert_eq!(
+            Command::LabelTopic {
+                topic: FilterKind::Crypto,
+                on_topic: false,
+                stories: vec![1, 2],
+            },
+            Command::from_args(&args(&["label", "crypto", "off", "1", "2"]))?
+        );
+        assert!(Command::from_args(&args(&["label", "crypto", "on"])).is_err());
+        assert!(Command::from_args(&args(&["label", "crypto", "yes", "1"]))
+            .is_err());
+        assert!(
+            Command::from_args(&args(&["label", "amfg", "on", "1"])).is_err()
+        );
+        assert!(
+            Command::from_args(&args(&["label", "crypto", "on", "x"])).is_err()
+        );
+        assert_eq!(
+            Command::TrainTopic {
+                topic: FilterKind::AiHype
+            },
+            Command::from_args(&args(&["train-topic", "aihype"]))?
+        );
+        assert_eq!(
+            Command::ExplainTopic {
+                topic: FilterKind::Politics,
+                story: 3
+            },
+            Command::from_args(&args(&["explain-topic", "politics", "3"]))?
+        );
+        assert!(
+            Command::from_args(&args(&["explain-topic", "politics"])).is_err()
+        );
+
+        assert_eq!(
+            Command::Stats { json: false },
+            Command::from_args(&args(&["stats"]))?
+        );
+        assert_eq!(
+            Command::Stats { json: true },
+            Command::from_args(&args(&["stats", "--json"]))?
+        );
+        assert!(Command::from_args(&args(&["stats", "--xml"])).is_err());
+
+        assert_eq!(
+            Command::Search {
+                query: "rust wasm*".to_string()
+            },
+            Command::from_args(&args(&["search", "rust", "wasm*"]))?
+        );
+        assert!(Command::from_args(&args(&["search"])).is_err());
+
+        assert!(Command::from_args(&args(&["nope"])).is_err());
+
+        Ok(())
+    }
+}
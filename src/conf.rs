@@ -1,6 +1,6 @@
 use {
     s3::{bucket::Bucket, creds::Credentials, Region},
-    std::{env, path::PathBuf},
+    std::{env, path::PathBuf, time::Duration},
 };
 
 #[derive(Debug)]
@@ -16,6 +16,83 @@ pub struct Conf {
     /// If set to true, we won't upload the html to S3 but instead store it into
     /// "pages" directory.
     pub store_html_locally: bool,
+    /// Identifies us to the APIs we call.
+    pub user_agent: String,
+    /// How long can a single http request take including reading the body.
+    pub http_timeout: Duration,
+    /// How long can establishing a connection take.
+    pub http_connect_timeout: Duration,
+    /// If set to true, instead of a single run we subscribe to the HN top
+    /// stories event stream and update pages as the front page changes.
+    pub stream_top_stories: bool,
+    /// In the streaming mode, how often at most do we re-render pages.
+    pub stream_min_interval: Duration,
+    /// Archive snapshots taken longer than this before or after the story
+    /// submission are ignored.
+    pub archive_max_age: Duration,
+    /// Whether to ask Wayback machine to capture urls which have no snapshot.
+    pub wayback_save_page_now: bool,
+    /// How many urls at most we ask Wayback machine to capture in a run.
+    pub wayback_save_limit: usize,
+    /// How long to wait between two capture requests.
+    pub wayback_save_interval: Duration,
+    /// For how long after insertion do we look again for missing snapshots.
+    pub archive_recheck_window: Duration,
+    /// How many stories at most we look again for missing snapshots in a run.
+    pub archive_recheck_limit: usize,
+    /// How many requests per second at most we send to Wayback machine.
+    pub wayback_requests_per_sec: f64,
+    /// How many Wayback machine requests can be in flight at once.
+    pub wayback_concurrency: usize,
+    /// How long a run can spend on Wayback machine requests. Snapshots which
+    /// we didn't get to are looked for in the following runs.
+    pub wayback_time_budget: Duration,
+    /// Whether to look for snapshots in archive.today besides Wayback machine.
+    pub archive_today: bool,
+    /// Names and url templates of additional archives to link to.
+    pub archive_templates: Vec<(String, String)>,
+    /// If set, each domain which we have at least this many stories from gets
+    /// a page with its latest stories.
+    pub site_pages_min_stories: Option<usize>,
+    /// For how long after a filter changed do pages using it show a notice.
+    pub filter_notice_period: Duration,
+    /// Whether to look for paywalls in the linked pages of new stories on
+    /// sites which aren't on the paywall list. The pages are fetched as with
+    /// `fetch_articles`.
+    pub paywall_detection: bool,
+    /// Lowercase domains with a paywall in addition to the built-in list.
+    pub paywall_domains: Vec<String>,
+    /// How many of the top stories we stored before get their points and
+    /// number of comments refreshed in a run.
+    pub score_refresh_limit: usize,
+    /// Whether to fetch the linked pages of new stories and extract the
+    /// article information for the filters.
+    pub fetch_articles: bool,
+    /// How much of a linked page we read at most.
+    pub article_max_bytes: usize,
+    /// How long can fetching a linked page take.
+    pub article_timeout: Duration,
+    /// How many past days, including today, the filter statistics cover.
+    pub stats_days: usize,
+    /// Whether to upload the search page along with the index of all stored
+    /// stories.
+    pub search_page: bool,
+    /// How many full years must pass between the end of the year of
+    /// publication and the submission for content to be flagged as old.
+    pub old_content_min_age_years: i64,
+    /// How many words must an article have at least to be a long read.
+    pub long_read_min_words: usize,
+    /// How many comments must a story have at least to be a flamewar.
+    pub flamewar_min_comments: i64,
+    /// A flamewar has more than this many comments per point.
+    pub flamewar_comments_per_point: f64,
+    /// Stories with fewer points are low signal once they're old enough.
+    pub low_signal_min_score: i64,
+    /// How many hours after the submission can a story be low signal.
+    pub low_signal_after_hours: i64,
+    /// Where trained models of the topic filters are stored, topic filters
+    /// flag nothing without it.
+    pub topic_models_dir: Option<PathBuf>,
 }
 
 impl Conf {
@@ -58,6 +135,286 @@ impl Conf {
             content_cache_header
         );
 
+        let user_agent = env::var(vars::USER_AGENT)
+            .ok()
+            .unwrap_or_else(|| defaults::USER_AGENT.to_string());
+        log::debug!("{}={:?}", vars::USER_AGENT, user_agent);
+
+        let http_timeout = env::var(vars::HTTP_TIMEOUT_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::HTTP_TIMEOUT);
+        log::debug!("{}={:?}", vars::HTTP_TIMEOUT_SECS, http_timeout);
+
+        let http_connect_timeout = env::var(vars::HTTP_CONNECT_TIMEOUT_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::HTTP_CONNECT_TIMEOUT);
+        log::debug!(
+            "{}={:?}",
+            vars::HTTP_CONNECT_TIMEOUT_SECS,
+            http_connect_timeout
+        );
+
+        let stream_top_stories = env::var(vars::STREAM_TOP_STORIES)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(false);
+        log::debug!("{}={:?}", vars::STREAM_TOP_STORIES, stream_top_stories);
+
+        let stream_min_interval = env::var(vars::STREAM_MIN_INTERVAL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::STREAM_MIN_INTERVAL);
+        log::debug!(
+            "{}={:?}",
+            vars::STREAM_MIN_INTERVAL_SECS,
+            stream_min_interval
+        );
+
+        let archive_max_age = env::var(vars::ARCHIVE_MAX_AGE_DAYS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap_or(defaults::ARCHIVE_MAX_AGE);
+        log::debug!("{}={:?}", vars::ARCHIVE_MAX_AGE_DAYS, archive_max_age);
+
+        // opt-out
+        let wayback_save_page_now = env::var(vars::WAYBACK_SAVE_PAGE_NOW)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(true);
+        log::debug!(
+            "{}={:?}",
+            vars::WAYBACK_SAVE_PAGE_NOW,
+            wayback_save_page_now
+        );
+
+        let wayback_save_limit = env::var(vars::WAYBACK_SAVE_LIMIT)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(defaults::WAYBACK_SAVE_LIMIT);
+        log::debug!("{}={:?}", vars::WAYBACK_SAVE_LIMIT, wayback_save_limit);
+
+        let wayback_save_interval = env::var(vars::WAYBACK_SAVE_INTERVAL_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::WAYBACK_SAVE_INTERVAL);
+        log::debug!(
+            "{}={:?}",
+            vars::WAYBACK_SAVE_INTERVAL_SECS,
+            wayback_save_interval
+        );
+
+        let archive_recheck_window = env::var(vars::ARCHIVE_RECHECK_DAYS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap_or(defaults::ARCHIVE_RECHECK_WINDOW);
+        log::debug!(
+            "{}={:?}",
+            vars::ARCHIVE_RECHECK_DAYS,
+            archive_recheck_window
+        );
+
+        let archive_recheck_limit = env::var(vars::ARCHIVE_RECHECK_LIMIT)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(defaults::ARCHIVE_RECHECK_LIMIT);
+        log::debug!(
+            "{}={:?}",
+            vars::ARCHIVE_RECHECK_LIMIT,
+            archive_recheck_limit
+        );
+
+        let wayback_requests_per_sec = env::var(vars::WAYBACK_REQUESTS_PER_SEC)
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(defaults::WAYBACK_REQUESTS_PER_SEC);
+        log::debug!(
+            "{}={:?}",
+            vars::WAYBACK_REQUESTS_PER_SEC,
+            wayback_requests_per_sec
+        );
+
+        let wayback_concurrency = env::var(vars::WAYBACK_CONCURRENCY)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(defaults::WAYBACK_CONCURRENCY);
+        log::debug!("{}={:?}", vars::WAYBACK_CONCURRENCY, wayback_concurrency);
+
+        let wayback_time_budget = env::var(vars::WAYBACK_TIME_BUDGET_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::WAYBACK_TIME_BUDGET);
+        log::debug!(
+            "{}={:?}",
+            vars::WAYBACK_TIME_BUDGET_SECS,
+            wayback_time_budget
+        );
+
+        let archive_today = env::var(vars::ARCHIVE_TODAY)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(false);
+        log::debug!("{}={:?}", vars::ARCHIVE_TODAY, archive_today);
+
+        // whitespace separated "name=template" pairs, the template cannot
+        // contain whitespace as it's an url
+        let archive_templates: Vec<_> = env::var(vars::ARCHIVE_TEMPLATES)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|pair| {
+                pair.split_once('=')
+                    .filter(|(name, template)| {
+                        !name.is_empty() && !template.is_empty()
+                    })
+                    .map(|(name, template)| {
+                        (name.to_string(), template.to_string())
+                    })
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "Invalid env var {}, expected name=template",
+                            vars::ARCHIVE_TEMPLATES
+                        )
+                    })
+            })
+            .collect();
+        log::debug!("{}={:?}", vars::ARCHIVE_TEMPLATES, archive_templates);
+
+        let site_pages_min_stories = env::var(vars::SITE_PAGES_MIN_STORIES)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok());
+        log::debug!(
+            "{}={:?}",
+            vars::SITE_PAGES_MIN_STORIES,
+            site_pages_min_stories
+        );
+
+        let filter_notice_period = env::var(vars::FILTER_NOTICE_DAYS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60))
+            .unwrap_or(defaults::FILTER_NOTICE_PERIOD);
+        log::debug!("{}={:?}", vars::FILTER_NOTICE_DAYS, filter_notice_period);
+
+        let paywall_detection = env::var(vars::PAYWALL_DETECTION)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(false);
+        log::debug!("{}={:?}", vars::PAYWALL_DETECTION, paywall_detection);
+
+        // whitespace separated, extend the list without a release
+        let paywall_domains: Vec<_> = env::var(vars::PAYWALL_DOMAINS)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|domain| domain.to_lowercase())
+            .collect();
+        log::debug!("{}={:?}", vars::PAYWALL_DOMAINS, paywall_domains);
+
+        let score_refresh_limit = env::var(vars::SCORE_REFRESH_LIMIT)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(defaults::SCORE_REFRESH_LIMIT);
+        log::debug!("{}={:?}", vars::SCORE_REFRESH_LIMIT, score_refresh_limit);
+
+        let fetch_articles = env::var(vars::FETCH_ARTICLES)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(false);
+        log::debug!("{}={:?}", vars::FETCH_ARTICLES, fetch_articles);
+
+        let article_max_bytes = env::var(vars::ARTICLE_MAX_KB)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .map(|kb| kb * 1024)
+            .unwrap_or(defaults::ARTICLE_MAX_BYTES);
+        log::debug!("{}={:?}", vars::ARTICLE_MAX_KB, article_max_bytes);
+
+        let article_timeout = env::var(vars::ARTICLE_TIMEOUT_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults::ARTICLE_TIMEOUT);
+        log::debug!("{}={:?}", vars::ARTICLE_TIMEOUT_SECS, article_timeout);
+
+        let stats_days = env::var(vars::STATS_DAYS)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .filter(|days| *days > 0)
+            .unwrap_or(defaults::STATS_DAYS);
+        log::debug!("{}={:?}", vars::STATS_DAYS, stats_days);
+
+        let search_page = env::var(vars::SEARCH_PAGE)
+            .map(|s| matches!(s.trim(), "ok" | "yes" | "1" | "true"))
+            .unwrap_or(false);
+        log::debug!("{}={:?}", vars::SEARCH_PAGE, search_page);
+
+        let old_content_min_age_years =
+            env::var(vars::OLD_CONTENT_MIN_AGE_YEARS)
+                .ok()
+                .and_then(|s| s.parse::<i64>().ok())
+                .unwrap_or(defaults::OLD_CONTENT_MIN_AGE_YEARS);
+        log::debug!(
+            "{}={:?}",
+            vars::OLD_CONTENT_MIN_AGE_YEARS,
+            old_content_min_age_years
+        );
+
+        let long_read_min_words = env::var(vars::LONG_READ_MIN_WORDS)
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(defaults::LONG_READ_MIN_WORDS);
+        log::debug!("{}={:?}", vars::LONG_READ_MIN_WORDS, long_read_min_words);
+
+        let flamewar_min_comments = env::var(vars::FLAMEWAR_MIN_COMMENTS)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(defaults::FLAMEWAR_MIN_COMMENTS);
+        log::debug!(
+            "{}={:?}",
+            vars::FLAMEWAR_MIN_COMMENTS,
+            flamewar_min_comments
+        );
+
+        let flamewar_comments_per_point =
+            env::var(vars::FLAMEWAR_COMMENTS_PER_POINT)
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .unwrap_or(defaults::FLAMEWAR_COMMENTS_PER_POINT);
+        log::debug!(
+            "{}={:?}",
+            vars::FLAMEWAR_COMMENTS_PER_POINT,
+            flamewar_comments_per_point
+        );
+
+        let low_signal_min_score = env::var(vars::LOW_SIGNAL_MIN_SCORE)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(defaults::LOW_SIGNAL_MIN_SCORE);
+        log::debug!(
+            "{}={:?}",
+            vars::LOW_SIGNAL_MIN_SCORE,
+            low_signal_min_score
+        );
+
+        let low_signal_after_hours = env::var(vars::LOW_SIGNAL_AFTER_HOURS)
+            .ok()
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(defaults::LOW_SIGNAL_AFTER_HOURS);
+        log::debug!(
+            "{}={:?}",
+            vars::LOW_SIGNAL_AFTER_HOURS,
+            low_signal_after_hours
+        );
+
+        let topic_models_dir = env::var(vars::TOPIC_MODELS_DIR)
+            .ok()
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from);
+        log::debug!("{}={:?}", vars::TOPIC_MODELS_DIR, topic_models_dir);
+
         let bucket_name = env::var(vars::BUCKET_NAME).unwrap_or_else(|_| {
             panic!("Missing env var {}.", vars::BUCKET_NAME)
         });
@@ -81,11 +438,44 @@ impl Conf {
         bucket.add_header("Cache-Control", &content_cache_header);
 
         Self {
+            archive_max_age,
+            article_max_bytes,
+            article_timeout,
+            archive_recheck_limit,
+            archive_recheck_window,
+            archive_templates,
+            archive_today,
             bucket,
+            fetch_articles,
+            filter_notice_period,
+            flamewar_comments_per_point,
+            flamewar_min_comments,
+            http_connect_timeout,
+            http_timeout,
+            long_read_min_words,
+            low_signal_after_hours,
+            low_signal_min_score,
             new_stories_limit,
+            old_content_min_age_years,
+            paywall_detection,
+            paywall_domains,
+            score_refresh_limit,
+            search_page,
+            site_pages_min_stories,
             sqlite_file,
+            stats_days,
             store_html_locally,
             stories_per_page,
+            stream_min_interval,
+            stream_top_stories,
+            topic_models_dir,
+            user_agent,
+            wayback_concurrency,
+            wayback_requests_per_sec,
+            wayback_save_interval,
+            wayback_save_limit,
+            wayback_save_page_now,
+            wayback_time_budget,
         }
     }
 }
@@ -98,10 +488,80 @@ mod vars {
     pub const NEW_STORIES_LIMIT: &str = "NEW_STORIES_LIMIT"; // opt
     pub const STORIES_PER_PAGE: &str = "STORIES_PER_PAGE"; // opt
     pub const CONTENT_CACHE_HEADER: &str = "CONTENT_CACHE_HEADER"; // opt
+    pub const USER_AGENT: &str = "USER_AGENT"; // opt
+    pub const HTTP_TIMEOUT_SECS: &str = "HTTP_TIMEOUT_SECS"; // opt
+    pub const HTTP_CONNECT_TIMEOUT_SECS: &str = "HTTP_CONNECT_TIMEOUT_SECS"; // opt
+    pub const STREAM_TOP_STORIES: &str = "STREAM_TOP_STORIES"; // opt
+    pub const STREAM_MIN_INTERVAL_SECS: &str = "STREAM_MIN_INTERVAL_SECS"; // opt
+    pub const ARCHIVE_MAX_AGE_DAYS: &str = "ARCHIVE_MAX_AGE_DAYS"; // opt
+    pub const WAYBACK_SAVE_PAGE_NOW: &str = "WAYBACK_SAVE_PAGE_NOW"; // opt
+    pub const WAYBACK_SAVE_LIMIT: &str = "WAYBACK_SAVE_LIMIT"; // opt
+    pub const WAYBACK_SAVE_INTERVAL_SECS: &str = "WAYBACK_SAVE_INTERVAL_SECS"; // opt
+    pub const ARCHIVE_RECHECK_DAYS: &str = "ARCHIVE_RECHECK_DAYS"; // opt
+    pub const ARCHIVE_RECHECK_LIMIT: &str = "ARCHIVE_RECHECK_LIMIT"; // opt
+    pub const ARCHIVE_TODAY: &str = "ARCHIVE_TODAY"; // opt
+    pub const ARCHIVE_TEMPLATES: &str = "ARCHIVE_TEMPLATES"; // opt
+    pub const WAYBACK_REQUESTS_PER_SEC: &str = "WAYBACK_REQUESTS_PER_SEC"; // opt
+    pub const WAYBACK_CONCURRENCY: &str = "WAYBACK_CONCURRENCY"; // opt
+    pub const WAYBACK_TIME_BUDGET_SECS: &str = "WAYBACK_TIME_BUDGET_SECS"; // opt
+    pub const SITE_PAGES_MIN_STORIES: &str = "SITE_PAGES_MIN_STORIES"; // opt
+    pub const FILTER_NOTICE_DAYS: &str = "FILTER_NOTICE_DAYS"; // opt
+    pub const PAYWALL_DETECTION: &str = "PAYWALL_DETECTION"; // opt
+    pub const PAYWALL_DOMAINS: &str = "PAYWALL_DOMAINS"; // opt
+    pub const SCORE_REFRESH_LIMIT: &str = "SCORE_REFRESH_LIMIT"; // opt
+    pub const FETCH_ARTICLES: &str = "FETCH_ARTICLES"; // opt
+    pub const ARTICLE_MAX_KB: &str = "ARTICLE_MAX_KB"; // opt
+    pub const ARTICLE_TIMEOUT_SECS: &str = "ARTICLE_TIMEOUT_SECS"; // opt
+    pub const STATS_DAYS: &str = "STATS_DAYS"; // opt
+    pub const SEARCH_PAGE: &str = "SEARCH_PAGE"; // opt
+    pub const OLD_CONTENT_MIN_AGE_YEARS: &str = "OLD_CONTENT_MIN_AGE_YEARS"; // opt
+    pub const LONG_READ_MIN_WORDS: &str = "LONG_READ_MIN_WORDS"; // opt
+    pub const FLAMEWAR_MIN_COMMENTS: &str = "FLAMEWAR_MIN_COMMENTS"; // opt
+    pub const FLAMEWAR_COMMENTS_PER_POINT: &str = "FLAMEWAR_COMMENTS_PER_POINT"; // opt
+    pub const LOW_SIGNAL_MIN_SCORE: &str = "LOW_SIGNAL_MIN_SCORE"; // opt
+    pub const LOW_SIGNAL_AFTER_HOURS: &str = "LOW_SIGNAL_AFTER_HOURS"; // opt
+    pub const TOPIC_MODELS_DIR: &str = "TOPIC_MODELS_DIR"; // opt
 }
 
-mod defaults {
+pub mod defaults {
+    use std::time::Duration;
+
     pub const NEW_STORIES_LIMIT: usize = 50;
     pub const STORIES_PER_PAGE: usize = 30;
     pub const CONTENT_CACHE_HEADER: &str = "public, max-age: 300";
+    pub const USER_AGENT: &str =
+        "sucklesshn (+https://github.com/bausano/suckless.hn)";
+    // the cron job is killed after 100s, a single request must not take a
+    // significant portion of that
+    pub const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+    pub const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+    pub const STREAM_MIN_INTERVAL: Duration = Duration::from_secs(60);
+    pub const ARCHIVE_MAX_AGE: Duration =
+        Duration::from_secs(30 * 24 * 60 * 60);
+    // anonymous save page now requests are heavily rate limited
+    pub const WAYBACK_SAVE_LIMIT: usize = 5;
+    pub const WAYBACK_SAVE_INTERVAL: Duration = Duration::from_secs(4);
+    pub const ARCHIVE_RECHECK_WINDOW: Duration =
+        Duration::from_secs(3 * 24 * 60 * 60);
+    pub const ARCHIVE_RECHECK_LIMIT: usize = 10;
+    pub const WAYBACK_REQUESTS_PER_SEC: f64 = 1.0;
+    pub const WAYBACK_CONCURRENCY: usize = 2;
+    // leaves enough of the cron job deadline for the rest of the run
+    pub const WAYBACK_TIME_BUDGET: Duration = Duration::from_secs(40);
+    pub const FILTER_NOTICE_PERIOD: Duration =
+        Duration::from_secs(30 * 24 * 60 * 60);
+    // stories further down the top list rarely make it to a page
+    pub const SCORE_REFRESH_LIMIT: usize = 100;
+    // the readable text is usually in the first few hundred kilobytes
+    pub const ARTICLE_MAX_BYTES: usize = 512 * 1024;
+    pub const ARTICLE_TIMEOUT: Duration = Duration::from_secs(5);
+    // four weeks show a trend without the page getting too long
+    pub const STATS_DAYS: usize = 28;
+    pub const OLD_CONTENT_MIN_AGE_YEARS: i64 = 1;
+    // about 13 minutes of reading
+    pub const LONG_READ_MIN_WORDS: usize = 3000;
+    pub const FLAMEWAR_MIN_COMMENTS: i64 = 40;
+    pub const FLAMEWAR_COMMENTS_PER_POINT: f64 = 1.5;
+    pub const LOW_SIGNAL_MIN_SCORE: i64 = 50;
+    pub const LOW_SIGNAL_AFTER_HOURS: i64 = 3;
 }
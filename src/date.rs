@@ -0,0 +1,170 @@
+//! We don't need a full blown date library, only conversions between unix time
+//! and calendar days. The algorithms are from [Howard Hinnant][chrono-algos].
+//!
+//! [chrono-algos]: http://howardhinnant.github.io/date_algorithms.html
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::prelude::*;
+
+pub const SECS_IN_DAY: i64 = 24 * 60 * 60;
+
+/// Current unix time.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Parses a day in format `YYYY-MM-DD` into unix time of its midnight (UTC).
+pub fn parse_day(day: &str) -> Result<i64> {
+    let parts: Vec<_> = day.trim().split('-').collect();
+    if parts.len() != 3 {
+        return Err(format!("Expected YYYY-MM-DD, got '{}'", day).into());
+    }
+
+    let year: i64 = parts[0].parse()?;
+    let month: i64 = parts[1].parse()?;
+    let day: i64 = parts[2].parse()?;
+    // e.g. 2021-02-31 would otherwise roll over to March
+    let days = days_from_civil(year, month, day);
+    if !(1..=12).contains(&month) || civil_from_days(days) != (year, month, day)
+    {
+        return Err(format!("Invalid date {}-{}-{}", year, month, day).into());
+    }
+
+    Ok(days * SECS_IN_DAY)
+}
+
+/// Returns number of days since 1970-01-01 for given calendar date.
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Returns calendar date `(year, month, day)` for given number of days since
+/// 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Calendar year of given unix time (UTC).
+pub fn year(time: i64) -> i64 {
+    civil_from_days(time.div_euclid(SECS_IN_DAY)).0
+}
+
+/// Formats unix time into day `YYYY-MM-DD` (UTC).
+pub fn format_day(time: i64) -> String {
+    let (year, month, day) = civil_from_days(time.div_euclid(SECS_IN_DAY));
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats unix time into Wayback machine timestamp `YYYYMMDDhhmmss` (UTC).
+pub fn to_wayback_timestamp(time: i64) -> String {
+    let (year, month, day) = civil_from_days(time.div_euclid(SECS_IN_DAY));
+    let secs = time.rem_euclid(SECS_IN_DAY);
+
+    format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Parses Wayback machine timestamp `YYYYMMDDhhmmss` into unix time. Wayback
+/// allows to omit the trailing parts, e.g. `YYYYMM`.
+pub fn parse_wayback_timestamp(timestamp: &str) -> Result<i64> {
+    let timestamp = timestamp.trim();
+    if timestamp.len() < 4
+        || timestamp.len() > 14
+        || !timestamp.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(format!("Invalid wayback timestamp {}", timestamp).into());
+    }
+
+    // missing parts default to the start of the period
+    let padded = format!("{:0<14}", timestamp);
+    let part = |from: usize, to: usize| -> i64 {
+        padded[from..to].parse().unwrap() // checked for digits above
+    };
+    let month = part(4, 6).max(1);
+    let day = part(6, 8).max(1);
+
+    Ok(days_from_civil(part(0, 4), month, day) * SECS_IN_DAY
+        + part(8, 10) * 3600
+        + part(10, 12) * 60
+        + part(12, 14))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_parses_day() -> Result<()> {
+        assert_eq!(0, parse_day("1970-01-01")?);
+        assert_eq!(951782400, parse_day("2000-02-29")?);
+        assert_eq!(1609459200, parse_day("2021-01-01")?);
+
+        assert!(parse_day("2021-13-01").is_err());
+        assert!(parse_day("2021-02-29").is_err());
+        assert!(parse_day("2021-02-31").is_err());
+        assert!(parse_day("2021-04-31").is_err());
+        assert!(parse_day("2021-01-00").is_err());
+        assert!(parse_day("2021/01/01").is_err());
+        assert!(parse_day("yesterday").is_err());
+
+        assert_eq!("2000-02-29", format_day(parse_day("2000-02-29")? + 3600));
+        assert_eq!("1969-12-31", format_day(-1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_converts_wayback_timestamps() -> Result<()> {
+        assert_eq!("19700101000000", to_wayback_timestamp(0));
+        assert_eq!("20130919044612", to_wayback_timestamp(1379565972));
+        assert_eq!("20000229235959", to_wayback_timestamp(951868799));
+
+        assert_eq!(1379565972, parse_wayback_timestamp("20130919044612")?);
+        assert_eq!(1377993600, parse_wayback_timestamp("201309")?);
+        assert!(parse_wayback_timestamp("2013-09-19").is_err());
+        assert!(parse_wayback_timestamp("").is_err());
+
+        for time in &[0, 951868799, 1379565972, 1609459200] {
+            let timestamp = to_wayback_timestamp(*time);
+            assert_eq!(*time, parse_wayback_timestamp(&timestamp)?);
+        }
+
+        Ok(())
+    }
+}
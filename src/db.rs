@@ -7,37 +7,144 @@
 //! * `title` is the displayed HN title, always present
 //! * `url` is either the article link or a link to the HN submission if
 //!     the submission text was given instead of url
-//! * `archive_url` is optional link to wayback machine snapshot or any other
-//!     url to alternative source
+//! * `canonical_url` is the `url` stripped of tracking params, `www.` etc.,
+//!     stories with the same canonical url are reposts of each other (indexed)
+//! * `domain` is the registrable domain of the article link, null for text
+//!     submissions (indexed)
+//! * `archive_checked_at` is optional unix time of when we last looked for a
+//!     Wayback snapshot of a story which didn't have one at insertion
 //! * `created_at` is a [unix time][sqlite-time] of when we inserted into db
+//! * `submitted_at` is an optional unix time of when the story was submitted
+//!     to HN
+//! * `lang` is the optional ISO 639-3 code of the language the story is
+//!     written in, e.g. "eng", see [`crate::lang`]
+//! * `score` and `comments` are the optional points and number of comments of
+//!     the story, refreshed while the story is among the top stories
+//!
+//! Older versions stored a single Wayback link in `archive_url` and
+//! `archived_at` columns. These are moved to `story_archives` when the table is
+//! created.
+//!
+//! # Table `story_archives`
+//! Links to archived versions of the story url, at most one per provider.
+//! * `story_id` is the HN id
+//! * `provider` is the name of the archive provider, e.g. "wayback"
+//! * `url` is the link to the archived version
+//! * `archived_at` is optional unix time of when the snapshot was taken
+//!
+//! # Table `story_articles`
+//! What we extracted from the linked page, only for stories whose page we
+//! fetched.
+//! * `story_id` is the HN id
+//! * `title` is the optional content of the `<title>` element
+//! * `description` is the optional meta description
+//! * `lang` is the optional language declared by the page, e.g. "en"
+//! * `word_count` is the number of words on the page
+//! * `text` is the beginning of the readable text of the page
+//!
+//! # Table `pending_captures`
+//! Urls which we asked Wayback machine to archive but whose snapshot wasn't
+//! available yet.
+//! * `story_id` is the HN id
+//! * `url` is the link to archive
+//! * `requested_at` is a unix time of when we asked for the capture
+//! * `checks` is how many times we've checked whether the snapshot exists
 //!
 //! # Table `story_filters`
 //! * `story_id` is the HN id
 //! * `amfg` is boolean set to 1 if filter flagged story
 //! * `askhn` is boolean set to 1 if filter flagged story
 //! * `bignews` is boolean set to 1 if filter flagged story
+//! * `old` is boolean set to 1 if filter flagged story, stories inserted
+//!     before the filter existed have 0
+//! * `audio`, `pdf` and `video` are booleans set to 1 if filter flagged story,
+//!     stories inserted before the filters existed have 0
+//! * `paywall` is boolean set to 1 if filter flagged story, stories inserted
+//!     before the filter existed have 0
+//! * `hiring`, `launchhn` and `tellhn` are booleans set to 1 if filter flagged
+//!     story, stories inserted before the filters existed have 0
+//! * `longread` is boolean set to 1 if filter flagged story, stories inserted
+//!     before the filter existed have 0
+//! * `nonenglish` is boolean set to 1 if filter flagged story, stories
+//!     inserted before the filter existed have 0
 //! * `showhn` is boolean set to 1 if filter flagged story
+//! * `aihype`, `crypto` and `politics` are booleans set to 1 if the topic
+//!     filter flagged story, stories inserted before the filters existed or
+//!     while the topic had no trained model have 0
+//!
+//! # Table `story_filter_reasons`
+//! Why a filter flagged a story, older versions didn't record this.
+//! * `story_id` is the HN id
+//! * `filter` is the name of the filter, e.g. "amfg"
+//! * `reason` is a human readable explanation, e.g. which keyword matched
+//!
+//! # Table `story_filter_versions`
+//! Which version of a filter flagged a story, older versions didn't record
+//! this.
+//! * `story_id` is the HN id
+//! * `filter` is the name of the filter, e.g. "amfg"
+//! * `version` is the [`crate::filter::Filter::version`] at the time of
+//!     insertion
+//!
+//! # Table `filter_versions`
+//! When we first ran with each version of a filter. Pages show a notice about
+//! a filter change for a while after it was deployed.
+//! * `filter` is the name of the filter, e.g. "amfg"
+//! * `version` is the [`crate::filter::Filter::version`]
+//! * `deployed_at` is a unix time of the first run with the version
+//!
+//! # Table `story_search`
+//! [FTS5][fts5] virtual table for the full-text search, see [`crate::search`].
+//! The rowid is the HN id. Filled from the existing stories when created.
+//! * `title` is the HN title
+//! * `text` is the `text` of the story article, empty if we didn't fetch it
+//!
+//! # Table `uploads`
+//! Objects which we only upload when the stored stories change, such as the
+//! search index, see [`crate::search`].
+//! * `object` is the path of the object, e.g. "search.json"
+//! * `story_count` is how many stories were stored at the upload
+//! * `uploaded_at` is a unix time of the upload
+//!
+//! # Table `story_topic_labels`
+//! Stories labelled by hand as on or off a topic, the training data of the
+//! topic filters, see [`crate::filter::topic`].
+//! * `story_id` is the HN id
+//! * `topic` is the name of the topic filter, e.g. "crypto"
+//! * `on_topic` is boolean set to 1 if the story is on the topic
+//! * `labelled_at` is a unix time of when the story was labelled
 //!
 //! [sqlite]: https://github.com/rusqlite/rusqlite
 //! [sqlite-time]: https://stackoverflow.com/q/200309/5093093#comment11501547_200329
+//! [fts5]: https://www.sqlite.org/fts5.html
+
+// list items in the docs above continue indented by four spaces
+#![allow(clippy::doc_overindented_list_items)]
 
 use {
     fallible_iterator::FallibleIterator,
     rusqlite::{params, Connection, OptionalExtension},
     std::{
-        convert::TryInto,
+        collections::{BTreeMap, HashMap, HashSet},
         time::{SystemTime, UNIX_EPOCH},
     },
 };
 
-use crate::{conf, filter::Filter, hn, prelude::*};
+use crate::{
+    canonical, conf, date, domain,
+    filter::{self, Filter},
+    hn,
+    prelude::*,
+};
+
+// How many earlier submissions of the same content we list with a story.
+const MAX_EARLIER_SUBMISSIONS: usize = 3;
 
 /// Creates sqlite connection to a file. If the file doesn't exist, creates
 /// necessary tables.
 pub fn conn(conf: &conf::Conf) -> Result<Connection> {
     let conn = Connection::open(&conf.sqlite_file)?;
-    create_table_stories(&conn)?;
-    create_table_story_filters(&conn)?;
+    create_tables(&conn)?;
 
     Ok(conn)
 }
@@ -59,7 +166,8 @@ pub fn insert_stories(
 
 /// Inserts story ids associated with filters which it passed into the database.
 /// Filters which flagged story are set to 1 (true), all other are defaulted to
-/// 0 (false).
+/// 0 (false). The reason and the filter version of each flag are stored in
+/// `story_filter_reasons` and `story_filter_versions`.
 pub fn insert_filters(
     conn: &Connection,
     filters: &[StoryFilters],
@@ -74,7 +182,7 @@ pub fn insert_filters(
             format!("INSERT INTO story_filters (story_id) VALUES ({});\n", id)
         } else {
             let filters_names: Vec<_> =
-                filters.iter().map(|f| f.name()).collect();
+                filters.iter().map(|(f, _)| f.name()).collect();
 
             format!(
                 "INSERT INTO story_filters (story_id, {}) VALUES ({}, {});\n",
@@ -95,9 +203,96 @@ pub fn insert_filters(
 
     conn.execute_batch(&sql)?;
 
+    // reasons are arbitrary strings, hence not part of the batch
+    let mut reason_stmt = conn.prepare(
+        "INSERT OR REPLACE INTO story_filter_reasons (story_id, filter, reason) \
+        VALUES (?1, ?2, ?3)",
+    )?;
+    let mut version_stmt = conn.prepare(
+        "INSERT OR REPLACE INTO story_filter_versions \
+        (story_id, filter, version) VALUES (?1, ?2, ?3)",
+    )?;
+    for (id, filters) in filters {
+        for (filter, reason) in filters {
+            reason_stmt.execute(params![id, filter.name(), reason])?;
+            version_stmt.execute(params![
+                id,
+                filter.name(),
+                filter.version()
+            ])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Counts stories flagged by the filter by the version of the filter which
+/// flagged them, the oldest first. Stories flagged before we recorded versions
+/// have none.
+pub fn select_flag_versions(
+    conn: &Connection,
+    filter: FilterKind,
+) -> Result<Vec<(Option<u32>, usize)>> {
+    // filter names are static strings, safe to interpolate
+    let mut stmt = conn.prepare(&format!(
+        "SELECT v.version, COUNT(*) FROM story_filters f \
+        LEFT JOIN story_filter_versions v \
+            ON v.story_id = f.story_id AND v.filter = ?1 \
+        WHERE f.{} = 1 GROUP BY v.version ORDER BY v.version",
+        filter.name()
+    ))?;
+    let versions = stmt
+        .query(params![filter.name()])?
+        .map(|row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))
+        .collect()?;
+
+    Ok(versions)
+}
+
+/// Records the current version of each filter unless we've already run with
+/// it.
+pub fn insert_filter_versions(conn: &Connection) -> Result<()> {
+    let now = date::now();
+    let mut stmt = conn.prepare(
+        "INSERT OR IGNORE INTO filter_versions (filter, version, deployed_at) \
+        VALUES (?1, ?2, ?3)",
+    )?;
+    for filter in filter::FILTERS.iter().chain(filter::DYNAMIC_FILTERS) {
+        stmt.execute(params![filter.name(), filter.version(), now])?;
+    }
+
     Ok(())
 }
 
+/// Returns the unix time of the first run with each recorded filter version.
+pub fn select_filter_versions(
+    conn: &Connection,
+) -> Result<HashMap<(FilterKind, u32), i64>> {
+    let by_name: HashMap<_, _> = filter::FILTERS
+        .iter()
+        .chain(filter::DYNAMIC_FILTERS)
+        .map(|filter| (filter.name(), *filter))
+        .collect();
+
+    let mut stmt = conn
+        .prepare("SELECT filter, version, deployed_at FROM filter_versions")?;
+    let rows: Vec<(String, u32, i64)> = stmt
+        .query([])?
+        .map(|row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .collect()?;
+
+    let versions = rows
+        .into_iter()
+        // skips filters which no longer exist
+        .filter_map(|(name, version, deployed_at)| {
+            let filter = by_name.get(name.as_str())?;
+            Some(((*filter, version), deployed_at))
+        })
+        .collect();
+
+    Ok(versions)
+}
+
 /// Given list of HN story ids, discards the ones we already store in db.
 pub fn only_new_stories(
     conn: &Connection,
@@ -142,188 +337,1523 @@ pub fn select_story(
     conn: &Connection,
     story_id: StoryId,
 ) -> Result<Option<StoryWithFilters>> {
-    // quite unfortunate that we have to have this gigantic return type
-    // alternative is to implement [`From`] [`Row`] for [`StoryWithFilters`].
-    let select_all_info = "
-        SELECT s.id, s.title, s.url, s.archive_url, \
-        sf.amfg, sf.askhn, sf.showhn, sf.bignews \
+    let select_all_info = format!(
+        "SELECT {} FROM stories AS s \
+        INNER JOIN story_filters AS sf ON s.id = sf.story_id \
+        WHERE s.id = ? LIMIT 1",
+        story_columns()
+    );
+
+    let story = conn
+        .query_row(&select_all_info, params![story_id], story_from_row)
+        .optional()?;
+
+    match story {
+        Some(mut story) => {
+            story.archives = select_archives(conn, story.id)?;
+            story.filter_reasons = select_filter_reasons(conn, &story)?;
+            story.earlier_submissions = select_earlier_submissions(
+                conn,
+                &story.canonical_url,
+                story.id,
+            )?;
+            Ok(Some(story))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Retrieves all stories which went through the filters along with their
+/// articles, most recent first. Unlike [`select_story`], archives, reasons and
+/// earlier submissions are left empty.
+pub fn select_stories_with_articles(
+    conn: &Connection,
+) -> Result<Vec<(StoryWithFilters, Option<Article>)>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, a.title, a.description, a.lang, a.word_count, a.text \
         FROM stories AS s \
         INNER JOIN story_filters AS sf ON s.id = sf.story_id \
-        WHERE s.id = ? LIMIT 1
-    ";
+        LEFT JOIN story_articles AS a ON s.id = a.story_id \
+        ORDER BY s.id DESC",
+        story_columns()
+    ))?;
+    let article_column = STORY_COLUMNS + filter::FILTERS.len();
+    let stories = stmt
+        .query([])?
+        .map(|row| {
+            let story = story_from_row(row)?;
+            // word count is not null for every stored article
+            let word_count: Option<i64> = row.get(article_column + 3)?;
+            let article = match word_count {
+                Some(word_count) => Some(Article {
+                    title: row.get(article_column)?,
+                    description: row.get(article_column + 1)?,
+                    lang: row.get(article_column + 2)?,
+                    word_count: word_count as usize,
+                    text: row.get(article_column + 4)?,
+                    paywall_marker: None,
+                }),
+                None => None,
+            };
+
+            Ok((story, article))
+        })
+        .collect()?;
+
+    Ok(stories)
+}
+
+// How many columns of the `stories` table precede the filter columns in
+// [`story_columns`].
+const STORY_COLUMNS: usize = 9;
+
+// Columns which [`story_from_row`] expects, `stories` table is aliased as `s`
+// and `story_filters` as `sf`.
+fn story_columns() -> String {
+    // each filter has a column of the same name in the story_filters table
+    let filter_columns: Vec<_> = filter::FILTERS
+        .iter()
+        .map(|filter| format!("sf.{}", filter.name()))
+        .collect();
+
+    format!(
+        "s.id, s.title, s.url, COALESCE(s.canonical_url, s.url), s.domain, \
+        s.submitted_at, s.lang, s.score, s.comments, {}",
+        filter_columns.join(", ")
+    )
+}
+
+// Story from a row which starts with [`story_columns`]. Archives, reasons and
+// earlier submissions are selected separately.
+fn story_from_row(row: &rusqlite::Row) -> rusqlite::Result<StoryWithFilters> {
     type RowData = (
         StoryId,
         String,
         String,
+        String,
         Option<String>,
-        bool,
-        bool,
-        bool,
-        bool,
+        Option<i64>,
+        Option<String>,
+        Option<i64>,
+        Option<i64>,
     );
 
-    let story = conn
-        .query_row(select_all_info, params![story_id], |row| {
-            let (id, title, url, archive_url, amfg, askhn, showhn, bignews): RowData
-             = row.try_into()?;
-
-            // only keeps filters which flagged the story
-            let filters = [
-                (amfg, FilterKind::BigTech),
-                (askhn, FilterKind::AskHn),
-                (showhn, FilterKind::ShowHn),
-                (bignews, FilterKind::LargeNewspaper),
-            ]
-            .iter()
-            .copied()
-            .filter_map(
-                |(applies, filter)| if applies { Some(filter) } else { None },
-            )
-            .collect();
+    let (
+        id,
+        title,
+        url,
+        canonical_url,
+        domain,
+        submitted_at,
+        lang,
+        score,
+        comments,
+    ): RowData = (
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+    );
 
-            Ok(StoryWithFilters {
-                id,
-                title,
-                url,
-                archive_url,
-                filters,
-            })
+    // only keeps filters which flagged the story
+    let mut filters = HashSet::new();
+    for (index, filter) in filter::FILTERS.iter().enumerate() {
+        if row.get(STORY_COLUMNS + index)? {
+            filters.insert(*filter);
+        }
+    }
+    let paywalled = filters.contains(&FilterKind::Paywall);
+
+    Ok(StoryWithFilters {
+        id,
+        title,
+        url,
+        canonical_url,
+        domain,
+        submitted_at,
+        lang,
+        score,
+        comments,
+        archives: vec![],
+        filters,
+        filter_reasons: BTreeMap::new(),
+        paywalled,
+        earlier_submissions: vec![],
+    })
+}
+
+// Reasons of the filters which flagged the story. Reasons of filters which no
+// longer flag the story are ignored.
+fn select_filter_reasons(
+    conn: &Connection,
+    story: &StoryWithFilters,
+) -> Result<BTreeMap<FilterKind, String>> {
+    let mut stmt = conn.prepare(
+        "SELECT filter, reason FROM story_filter_reasons WHERE story_id = ?1",
+    )?;
+    let reasons = stmt
+        .query(params![story.id])?
+        .map(|row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .filter_map(|(name, reason)| {
+            let filter = story.filters.iter().find(|f| f.name() == name);
+            Ok(filter.map(|filter| (*filter, reason)))
         })
-        .optional()?;
+        .collect()?;
 
-    Ok(story)
+    Ok(reasons)
 }
 
-// Creates table `stories` if it doesn't exist yet. See the module docs for
-// the fields description.
-fn create_table_stories(conn: &Connection) -> Result<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS stories (
-            id              INTEGER PRIMARY KEY,
-            title           TEXT NOT NULL,
-            url             TEXT NOT NULL,
-            archive_url     TEXT,
-            created_at      INTEGER(4)
-        )",
-        [],
+/// Stores the current points and number of comments of given stories. Stories
+/// which we don't store are ignored.
+pub fn update_scores(conn: &Connection, stories: &[Story]) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "UPDATE stories SET score = ?1, comments = ?2 WHERE id = ?3",
     )?;
+    for story in stories {
+        stmt.execute(params![story.score, story.comments, story.id])?;
+    }
 
     Ok(())
 }
 
-// Creates table `story_filters` if it doesn't exist yet. See the module docs
-// for the fields description.
-//
-// TODO: How do we migrate to new filters?
-fn create_table_story_filters(conn: &Connection) -> Result<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS story_filters (
-            story_id        INTEGER PRIMARY KEY,
-            amfg            INTEGER(1) NOT NULL DEFAULT 0,
-            askhn           INTEGER(1) NOT NULL DEFAULT 0,
-            bignews         INTEGER(1) NOT NULL DEFAULT 0,
-            showhn          INTEGER(1) NOT NULL DEFAULT 0,
-            FOREIGN KEY(story_id) REFERENCES stories(id)
-        )",
-        [],
+/// Returns ids of the latest stories from given domain, most recent first.
+pub fn select_stories_from_domain(
+    conn: &Connection,
+    domain: &str,
+    limit: usize,
+) -> Result<Vec<StoryId>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM stories WHERE domain = ?1 ORDER BY id DESC LIMIT ?2",
     )?;
+    let ids = stmt
+        .query(params![domain, limit as i64])?
+        .map(|row| row.get(0))
+        .collect()?;
 
-    Ok(())
+    Ok(ids)
 }
 
-/// Inserts given story into the db. A submission with link will have url
-/// pointing to the article, a text submission to the HN post.
-fn insert_story(conn: &Connection, story: Story) -> Result<()> {
-    let Story {
-        id,
-        title,
-        kind,
-        archive_url,
-    } = story;
-    log::trace!("Inserting story {}", id);
+/// Returns domains which we have at least given number of stories from,
+/// sorted alphabetically.
+pub fn select_frequent_domains(
+    conn: &Connection,
+    min_stories: usize,
+) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT domain FROM stories WHERE domain IS NOT NULL \
+        GROUP BY domain HAVING COUNT(*) >= ?1 ORDER BY domain ASC",
+    )?;
+    let domains = stmt
+        .query(params![min_stories as i64])?
+        .map(|row| row.get(0))
+        .collect()?;
 
-    let url = match kind {
-        StoryKind::Url(url) => url,
-        StoryKind::Text(_) => hn::submission_url(id),
-    };
+    Ok(domains)
+}
 
-    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+/// Returns stories with given canonical url which were submitted before the
+/// given story, most recent first.
+pub fn select_earlier_submissions(
+    conn: &Connection,
+    canonical_url: &str,
+    story_id: StoryId,
+) -> Result<Vec<EarlierSubmission>> {
+    // HN ids are increasing, therefore lower id means earlier submission
+    let mut stmt = conn.prepare(
+        "SELECT id, title, submitted_at FROM stories \
+        WHERE canonical_url = ?1 AND id < ?2 ORDER BY id DESC LIMIT ?3",
+    )?;
+    let submissions = stmt
+        .query(params![
+            canonical_url,
+            story_id,
+            MAX_EARLIER_SUBMISSIONS as i64
+        ])?
+        .map(|row| {
+            let id = row.get(0)?;
+            Ok(EarlierSubmission {
+                id,
+                title: row.get(1)?,
+                url: hn::submission_url(id),
+                submitted_on: row
+                    .get::<_, Option<i64>>(2)?
+                    .map(date::format_day),
+            })
+        })
+        .collect()?;
+
+    Ok(submissions)
+}
 
+/// Returns archive links of a story ordered by provider name.
+pub fn select_archives(
+    conn: &Connection,
+    story_id: StoryId,
+) -> Result<Vec<ArchiveLink>> {
     let mut stmt = conn.prepare(
-        "INSERT INTO stories (id, title, url, archive_url, created_at) \
-        VALUES (?1, ?2, ?3, ?4, ?5)",
+        "SELECT provider, url, archived_at FROM story_archives \
+        WHERE story_id = ?1 ORDER BY provider ASC",
     )?;
-    // sqlite doesn't support unsigned ints
-    stmt.execute(params![id, title, url, archive_url, created_at as i64])?;
+    let archives = stmt
+        .query(params![story_id])?
+        .map(|row| {
+            Ok(ArchiveLink {
+                provider: row.get(0)?,
+                url: row.get(1)?,
+                archived_at: row.get(2)?,
+            })
+        })
+        .collect()?;
+
+    Ok(archives)
+}
+
+/// Labels stories as on or off the topic of a topic filter. A story labelled
+/// again keeps only the latest label.
+pub fn insert_topic_labels(
+    conn: &Connection,
+    topic: FilterKind,
+    on_topic: bool,
+    story_ids: &[StoryId],
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO story_topic_labels \
+        (story_id, topic, on_topic, labelled_at) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    let now = date::now();
+    for story_id in story_ids {
+        stmt.execute(params![story_id, topic.name(), on_topic, now])?;
+    }
 
     Ok(())
 }
 
-#[cfg(test)]
-pub mod tests {
-    use super::*;
+/// Returns stories stored since given unix time ordered by the time of
+/// insertion, each with the filters which flagged it.
+pub fn select_flagged_stories(
+    conn: &Connection,
+    since: i64,
+) -> Result<Vec<FlaggedStory>> {
+    let filter_columns: Vec<_> = filter::FILTERS
+        .iter()
+        .map(|filter| format!("sf.{}", filter.name()))
+        .collect();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT s.id, s.created_at, s.domain, {} \
+        FROM stories AS s \
+        INNER JOIN story_filters AS sf ON s.id = sf.story_id \
+        WHERE s.created_at >= ?1 ORDER BY s.created_at ASC, s.id ASC",
+        filter_columns.join(", ")
+    ))?;
+    let mut stories: Vec<FlaggedStory> = stmt
+        .query(params![since])?
+        .map(|row| {
+            let mut filters = vec![];
+            for (index, filter) in filter::FILTERS.iter().enumerate() {
+                if row.get(3 + index)? {
+                    filters.push((*filter, None));
+                }
+            }
 
-    pub fn test_conn() -> Result<Connection> {
-        let conn = Connection::open_in_memory()?;
-        create_table_stories(&conn)?;
-        create_table_story_filters(&conn)?;
-        Ok(conn)
+            Ok(FlaggedStory {
+                id: row.get(0)?,
+                created_at: row.get(1)?,
+                domain: row.get(2)?,
+                filters,
+            })
+        })
+        .collect()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT r.story_id, r.filter, r.reason \
+        FROM story_filter_reasons AS r \
+        INNER JOIN stories AS s ON s.id = r.story_id \
+        WHERE s.created_at >= ?1",
+    )?;
+    let mut reasons: HashMap<(StoryId, String), String> = stmt
+        .query(params![since])?
+        .map(|row| Ok(((row.get(0)?, row.get(1)?), row.get(2)?)))
+        .collect()?;
+    for story in &mut stories {
+        for (filter, reason) in &mut story.filters {
+            *reason = reasons.remove(&(story.id, filter.name().to_string()));
+        }
     }
 
-    #[test]
-    fn it_returns_only_new_stories() -> Result<()> {
-        let conn = test_conn()?;
+    Ok(stories)
+}
 
-        assert_eq!(vec![1, 2, 3], only_new_stories(&conn, &vec![1, 2, 3])?);
+/// Returns stored stories matching the [FTS5 query][fts5-query], the best
+/// match first. Matches in the title weigh more than in the article text.
+///
+/// [fts5-query]: https://www.sqlite.org/fts5.html#full_text_query_syntax
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.title, s.url, s.domain, s.submitted_at, \
+        snippet(story_search, -1, '[', ']', '…', 12) \
+        FROM story_search \
+        INNER JOIN stories AS s ON s.id = story_search.rowid \
+        WHERE story_search MATCH ?1 \
+        ORDER BY bm25(story_search, 10.0, 1.0) LIMIT ?2",
+    )?;
+    let results = stmt
+        .query(params![query, limit as i64])?
+        .map(|row| {
+            Ok(SearchResult {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                url: row.get(2)?,
+                domain: row.get(3)?,
+                submitted_at: row.get(4)?,
+                snippet: row.get(5)?,
+            })
+        })
+        .collect()?;
 
-        let story1 = Story::random_url();
-        let story1_id = story1.id;
-        insert_story(&conn, story1)?;
+    Ok(results)
+}
 
-        let story2 = Story::random_url();
-        let story2_id = story2.id;
-        insert_story(&conn, story2)?;
+/// Returns all stored stories without snippets, the latest first.
+pub fn select_search_index(conn: &Connection) -> Result<Vec<SearchResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, title, url, domain, submitted_at FROM stories \
+        ORDER BY created_at DESC, id DESC",
+    )?;
+    let index = stmt
+        .query([])?
+        .map(|row| {
+            Ok(SearchResult {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                url: row.get(2)?,
+                domain: row.get(3)?,
+                submitted_at: row.get(4)?,
+                snippet: None,
+            })
+        })
+        .collect()?;
 
-        assert_eq!(
-            vec![1],
-            only_new_stories(&conn, &vec![1, story1_id, story2_id])?
-        );
+    Ok(index)
+}
 
-        Ok(())
-    }
+/// How many stories are stored.
+pub fn count_stories(conn: &Connection) -> Result<usize> {
+    let count: i64 =
+        conn.query_row("SELECT COUNT(*) FROM stories", [], |row| row.get(0))?;
 
-    #[test]
-    fn it_selects_story() -> Result<()> {
-        let conn = test_conn()?;
+    Ok(count as usize)
+}
 
-        let stories = &[
-            (
-                Story::random_url(),
-                vec![FilterKind::AskHn, FilterKind::ShowHn],
-            ),
-            (Story::random_url(), vec![FilterKind::LargeNewspaper]),
-            (Story::random_url(), vec![]),
-        ];
-        insert_test_data(&conn, stories)?;
+/// Returns how many stories were stored when we last uploaded the object, if
+/// ever.
+pub fn select_upload_story_count(
+    conn: &Connection,
+    object: &str,
+) -> Result<Option<usize>> {
+    let count: Option<i64> = conn
+        .query_row(
+            "SELECT story_count FROM uploads WHERE object = ?1",
+            params![object],
+            |row| row.get(0),
+        )
+        .optional()?;
 
-        // for each story, gets it from db and asserts it equals the test data
-        for (story, filters) in stories {
-            let db_story = select_story(&conn, story.id)?.unwrap();
+    Ok(count.map(|count| count as usize))
+}
 
-            assert_eq!(story.id, db_story.id);
-            assert_eq!(&story.title, &db_story.title);
+/// Records that we uploaded the object when given number of stories was
+/// stored.
+pub fn insert_upload(
+    conn: &Connection,
+    object: &str,
+    story_count: usize,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO uploads (object, story_count, uploaded_at) \
+        VALUES (?1, ?2, ?3)",
+        params![object, story_count as i64, date::now()],
+    )?;
 
-            assert_eq!(filters.len(), db_story.filters.len());
-            for filter in filters {
-                assert!(db_story.filters.contains(filter));
-            }
-        }
+    Ok(())
+}
 
-        Ok(())
-    }
+/// Returns ids of the stories labelled with the topic ordered by id, each with
+/// whether it's on topic.
+pub fn select_topic_labels(
+    conn: &Connection,
+    topic: FilterKind,
+) -> Result<Vec<(StoryId, bool)>> {
+    let mut stmt = conn.prepare(
+        "SELECT story_id, on_topic FROM story_topic_labels \
+        WHERE topic = ?1 ORDER BY story_id ASC",
+    )?;
+    let labels = stmt
+        .query(params![topic.name()])?
+        .map(|row| Ok((row.get(0)?, row.get(1)?)))
+        .collect()?;
 
-    /// Inserts given stories + filters to the database.
-    pub fn insert_test_data(
+    Ok(labels)
+}
+
+/// Remembers that we asked for a snapshot of given urls.
+pub fn insert_pending_captures(
+    conn: &Connection,
+    captures: &[(StoryId, String)],
+) -> Result<()> {
+    let requested_at = date::now();
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO pending_captures \
+        (story_id, url, requested_at, checks) VALUES (?1, ?2, ?3, 0)",
+    )?;
+    for (story_id, url) in captures {
+        stmt.execute(params![story_id, url, requested_at])?;
+    }
+
+    Ok(())
+}
+
+/// Returns captures requested before given unix time, oldest first.
+pub fn select_pending_captures(
+    conn: &Connection,
+    requested_before: i64,
+    limit: usize,
+) -> Result<Vec<PendingCapture>> {
+    let mut stmt = conn.prepare(
+        "SELECT story_id, url, requested_at, checks FROM pending_captures \
+        WHERE requested_at < ?1 ORDER BY requested_at ASC LIMIT ?2",
+    )?;
+    let captures = stmt
+        .query(params![requested_before, limit as i64])?
+        .map(|row| {
+            Ok(PendingCapture {
+                story_id: row.get(0)?,
+                url: row.get(1)?,
+                requested_at: row.get(2)?,
+                checks: row.get(3)?,
+            })
+        })
+        .collect()?;
+
+    Ok(captures)
+}
+
+/// Records that we checked for the snapshot and it wasn't there yet.
+pub fn increment_capture_checks(
+    conn: &Connection,
+    story_id: StoryId,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE pending_captures SET checks = checks + 1 WHERE story_id = ?1",
+        params![story_id],
+    )?;
+
+    Ok(())
+}
+
+/// Stops tracking the capture, either because the snapshot is available or
+/// because we gave up.
+pub fn delete_pending_capture(
+    conn: &Connection,
+    story_id: StoryId,
+) -> Result<()> {
+    conn.execute(
+        "DELETE FROM pending_captures WHERE story_id = ?1",
+        params![story_id],
+    )?;
+
+    Ok(())
+}
+
+/// Sets the archive link of an already stored story, replacing any previous
+/// link from the same provider.
+pub fn update_archive(
+    conn: &Connection,
+    story_id: StoryId,
+    archive: &ArchiveLink,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO story_archives \
+        (story_id, provider, url, archived_at) VALUES (?1, ?2, ?3, ?4)",
+        params![story_id, archive.provider, archive.url, archive.archived_at],
+    )?;
+
+    Ok(())
+}
+
+/// Returns url stories inserted after given unix time which have no archive
+/// link from given provider, which weren't checked since `checked_before` and
+/// for which no capture is pending. Stories which we haven't checked for the
+/// longest time come first.
+pub fn select_stories_without_archive(
+    conn: &Connection,
+    provider: &str,
+    created_after: i64,
+    checked_before: i64,
+    limit: usize,
+) -> Result<Vec<Story>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, title, url, submitted_at FROM stories \
+        WHERE created_at > ?1 AND url != (?2 || id) \
+        AND (archive_checked_at IS NULL OR archive_checked_at < ?3) \
+        AND id NOT IN (SELECT story_id FROM pending_captures) \
+        AND id NOT IN \
+            (SELECT story_id FROM story_archives WHERE provider = ?5) \
+        ORDER BY archive_checked_at IS NOT NULL, archive_checked_at ASC \
+        LIMIT ?4",
+    )?;
+    // text submissions store link to HN instead of url
+    let hn_submission_prefix = hn::submission_url(0);
+    let hn_submission_prefix = hn_submission_prefix.trim_end_matches('0');
+
+    let stories = stmt
+        .query(params![
+            created_after,
+            hn_submission_prefix,
+            checked_before,
+            limit as i64,
+            provider
+        ])?
+        .map(|row| {
+            Ok(Story {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                kind: StoryKind::Url(row.get(2)?),
+                submitted_at: row.get(3)?,
+                score: None,
+                comments: None,
+                archives: vec![],
+                article: None,
+            })
+        })
+        .collect()?;
+
+    Ok(stories)
+}
+
+/// Marks stories as never checked for a snapshot, therefore they're first in
+/// line when we look again for missing snapshots.
+pub fn defer_archive_check(
+    conn: &Connection,
+    story_ids: &[StoryId],
+) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "UPDATE stories SET archive_checked_at = NULL WHERE id = ?1",
+    )?;
+    for story_id in story_ids {
+        stmt.execute(params![story_id])?;
+    }
+
+    Ok(())
+}
+
+/// Records that we looked for a snapshot of the story.
+pub fn update_archive_checked_at(
+    conn: &Connection,
+    story_id: StoryId,
+) -> Result<()> {
+    conn.execute(
+        "UPDATE stories SET archive_checked_at = ?1 WHERE id = ?2",
+        params![date::now(), story_id],
+    )?;
+
+    Ok(())
+}
+
+fn create_tables(conn: &Connection) -> Result<()> {
+    create_table_stories(conn)?;
+    create_table_story_filters(conn)?;
+    create_table_story_filter_reasons(conn)?;
+    create_table_story_filter_versions(conn)?;
+    create_table_filter_versions(conn)?;
+    create_table_story_archives(conn)?;
+    create_table_story_articles(conn)?;
+    create_table_pending_captures(conn)?;
+    create_table_story_topic_labels(conn)?;
+    create_table_story_search(conn)?;
+    create_table_uploads(conn)?;
+
+    Ok(())
+}
+
+// Creates table `stories` if it doesn't exist yet. See the module docs for
+// the fields description.
+fn create_table_stories(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stories (
+            id              INTEGER PRIMARY KEY,
+            title           TEXT NOT NULL,
+            url             TEXT NOT NULL,
+            canonical_url   TEXT,
+            domain          TEXT,
+            archive_checked_at  INTEGER(4),
+            created_at      INTEGER(4),
+            submitted_at    INTEGER(4),
+            score           INTEGER,
+            comments        INTEGER,
+            lang            TEXT
+        )",
+        [],
+    )?;
+    add_column_if_missing(conn, "stories", "submitted_at", "INTEGER(4)")?;
+    add_column_if_missing(conn, "stories", "archive_checked_at", "INTEGER(4)")?;
+    add_column_if_missing(conn, "stories", "canonical_url", "TEXT")?;
+    let domain_added =
+        add_column_if_missing(conn, "stories", "domain", "TEXT")?;
+    add_column_if_missing(conn, "stories", "score", "INTEGER")?;
+    add_column_if_missing(conn, "stories", "comments", "INTEGER")?;
+    add_column_if_missing(conn, "stories", "lang", "TEXT")?;
+    backfill_canonical_urls(conn)?;
+    if domain_added {
+        backfill_domains(conn)?;
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS stories_canonical_url \
+            ON stories (canonical_url);
+        CREATE INDEX IF NOT EXISTS stories_domain ON stories (domain);",
+    )?;
+
+    Ok(())
+}
+
+// Stories inserted by older versions have no canonical url.
+fn backfill_canonical_urls(conn: &Connection) -> Result<()> {
+    let mut stmt = conn
+        .prepare("SELECT id, url FROM stories WHERE canonical_url IS NULL")?;
+    let stories: Vec<(StoryId, String)> = stmt
+        .query([])?
+        .map(|row| Ok((row.get(0)?, row.get(1)?)))
+        .collect()?;
+    if stories.is_empty() {
+        return Ok(());
+    }
+
+    log::info!("Computing canonical url of {} stories", stories.len());
+    let mut stmt =
+        conn.prepare("UPDATE stories SET canonical_url = ?1 WHERE id = ?2")?;
+    for (id, url) in stories {
+        stmt.execute(params![canonical::url(&url), id])?;
+    }
+
+    Ok(())
+}
+
+// Stories inserted by older versions have no domain. This runs only once, when
+// the column is added, because text submissions and urls without a registrable
+// domain stay null. Text submissions are recognized by the link to HN.
+fn backfill_domains(conn: &Connection) -> Result<()> {
+    let hn_submission_prefix = hn::submission_url(0);
+    let hn_submission_prefix = hn_submission_prefix.trim_end_matches('0');
+
+    let mut stmt = conn.prepare(
+        "SELECT id, url FROM stories \
+        WHERE domain IS NULL AND url NOT LIKE (?1 || '%')",
+    )?;
+    let stories: Vec<(StoryId, String)> = stmt
+        .query(params![hn_submission_prefix])?
+        .map(|row| Ok((row.get(0)?, row.get(1)?)))
+        .collect()?;
+
+    let mut stmt =
+        conn.prepare("UPDATE stories SET domain = ?1 WHERE id = ?2")?;
+    for (id, url) in stories {
+        if let Some(domain) = domain::registrable(&url) {
+            stmt.execute(params![domain, id])?;
+        }
+    }
+
+    Ok(())
+}
+
+// Creates table `story_filters` if it doesn't exist yet. See the module docs
+// for the fields description.
+//
+// TODO: How do we migrate to new filters?
+fn create_table_story_filters(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_filters (
+            story_id        INTEGER PRIMARY KEY,
+            aihype          INTEGER(1) NOT NULL DEFAULT 0,
+            amfg            INTEGER(1) NOT NULL DEFAULT 0,
+            askhn           INTEGER(1) NOT NULL DEFAULT 0,
+            audio           INTEGER(1) NOT NULL DEFAULT 0,
+            bignews         INTEGER(1) NOT NULL DEFAULT 0,
+            crypto          INTEGER(1) NOT NULL DEFAULT 0,
+            hiring          INTEGER(1) NOT NULL DEFAULT 0,
+            launchhn        INTEGER(1) NOT NULL DEFAULT 0,
+            longread        INTEGER(1) NOT NULL DEFAULT 0,
+            nonenglish      INTEGER(1) NOT NULL DEFAULT 0,
+            old             INTEGER(1) NOT NULL DEFAULT 0,
+            paywall         INTEGER(1) NOT NULL DEFAULT 0,
+            pdf             INTEGER(1) NOT NULL DEFAULT 0,
+            politics        INTEGER(1) NOT NULL DEFAULT 0,
+            showhn          INTEGER(1) NOT NULL DEFAULT 0,
+            tellhn          INTEGER(1) NOT NULL DEFAULT 0,
+            video           INTEGER(1) NOT NULL DEFAULT 0,
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+    // filters added after the table was first created
+    for column in &[
+        "old",
+        "audio",
+        "pdf",
+        "video",
+        "paywall",
+        "tellhn",
+        "launchhn",
+        "hiring",
+        "longread",
+        "nonenglish",
+        "aihype",
+        "crypto",
+        "politics",
+    ] {
+        add_column_if_missing(
+            conn,
+            "story_filters",
+            column,
+            "INTEGER(1) NOT NULL DEFAULT 0",
+        )?;
+    }
+
+    Ok(())
+}
+
+// Creates table `story_filter_reasons` if it doesn't exist yet. See the module
+// docs for the fields description.
+fn create_table_story_filter_reasons(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_filter_reasons (
+            story_id        INTEGER NOT NULL,
+            filter          TEXT NOT NULL,
+            reason          TEXT NOT NULL,
+            PRIMARY KEY(story_id, filter),
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Creates table `story_filter_versions` if it doesn't exist yet. See the
+// module docs for the fields description.
+fn create_table_story_filter_versions(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_filter_versions (
+            story_id        INTEGER NOT NULL,
+            filter          TEXT NOT NULL,
+            version         INTEGER NOT NULL,
+            PRIMARY KEY(story_id, filter),
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Creates table `filter_versions` if it doesn't exist yet. See the module docs
+// for the fields description.
+fn create_table_filter_versions(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS filter_versions (
+            filter          TEXT NOT NULL,
+            version         INTEGER NOT NULL,
+            deployed_at     INTEGER(4) NOT NULL,
+            PRIMARY KEY(filter, version)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Tables are always created with all columns, but databases created by older
+// versions need to be migrated. Sqlite supports adding a column, which is
+// enough for our needs. Returns whether the column was added.
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<bool> {
+    if has_column(conn, table, column)? {
+        return Ok(false);
+    }
+
+    log::info!("Adding column {}.{}", table, column);
+    conn.execute(
+        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        [],
+    )?;
+
+    Ok(true)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query([])?
+        // second column of the pragma is the name of the column
+        .map(|r| r.get::<_, String>(1))
+        .any(|name| Ok(name == column))?;
+
+    Ok(exists)
+}
+
+// Creates table `story_articles` if it doesn't exist yet. See the module docs
+// for the fields description.
+fn create_table_story_articles(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_articles (
+            story_id        INTEGER PRIMARY KEY,
+            title           TEXT,
+            description     TEXT,
+            lang            TEXT,
+            word_count      INTEGER NOT NULL,
+            text            TEXT NOT NULL,
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Creates table `story_search` if it doesn't exist yet and indexes the stories
+// stored so far. See the module docs for the fields description.
+fn create_table_story_search(conn: &Connection) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master \
+        WHERE type = 'table' AND name = 'story_search')",
+        [],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Ok(());
+    }
+
+    // diacritics are removed so that "cafe" finds "café"
+    conn.execute(
+        "CREATE VIRTUAL TABLE story_search USING fts5(
+            title,
+            text,
+            tokenize = 'unicode61 remove_diacritics 2'
+        )",
+        [],
+    )?;
+    let indexed = conn.execute(
+        "INSERT INTO story_search (rowid, title, text) \
+        SELECT s.id, s.title, COALESCE(a.text, '') FROM stories AS s \
+        LEFT JOIN story_articles AS a ON a.story_id = s.id",
+        [],
+    )?;
+    log::info!("Indexed {} stories for search", indexed);
+
+    Ok(())
+}
+
+// Creates table `uploads` if it doesn't exist yet. See the module docs for
+// the fields description.
+fn create_table_uploads(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS uploads (
+            object          TEXT PRIMARY KEY,
+            story_count     INTEGER NOT NULL,
+            uploaded_at     INTEGER(4) NOT NULL
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Creates table `story_topic_labels` if it doesn't exist yet. See the module
+// docs for the fields description.
+fn create_table_story_topic_labels(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_topic_labels (
+            story_id        INTEGER NOT NULL,
+            topic           TEXT NOT NULL,
+            on_topic        INTEGER(1) NOT NULL,
+            labelled_at     INTEGER(4) NOT NULL,
+            PRIMARY KEY(story_id, topic),
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+// Creates table `story_archives` if it doesn't exist yet. See the module docs
+// for the fields description. Databases created by older versions have the
+// wayback links in the `stories` table, these are copied over.
+fn create_table_story_archives(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS story_archives (
+            story_id        INTEGER NOT NULL,
+            provider        TEXT NOT NULL,
+            url             TEXT NOT NULL,
+            archived_at     INTEGER(4),
+            PRIMARY KEY(story_id, provider),
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    // the old column is never dropped, therefore we only copy if the table is
+    // still empty
+    let is_empty: bool = conn.query_row(
+        "SELECT NOT EXISTS (SELECT 1 FROM story_archives)",
+        [],
+        |row| row.get(0),
+    )?;
+    if is_empty && has_column(conn, "stories", "archive_url")? {
+        // the oldest versions didn't store the snapshot time
+        let archived_at = if has_column(conn, "stories", "archived_at")? {
+            "archived_at"
+        } else {
+            "NULL"
+        };
+        let migrated = conn.execute(
+            &format!(
+                "INSERT INTO story_archives \
+                (story_id, provider, url, archived_at) \
+                SELECT id, 'wayback', archive_url, {} FROM stories \
+                WHERE archive_url IS NOT NULL",
+                archived_at
+            ),
+            [],
+        )?;
+        log::info!("Migrated {} archive links to story_archives", migrated);
+    }
+
+    Ok(())
+}
+
+// Creates table `pending_captures` if it doesn't exist yet. See the module
+// docs for the fields description.
+fn create_table_pending_captures(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pending_captures (
+            story_id        INTEGER PRIMARY KEY,
+            url             TEXT NOT NULL,
+            requested_at    INTEGER(4) NOT NULL,
+            checks          INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY(story_id) REFERENCES stories(id)
+        )",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Inserts given story into the db. A submission with link will have url
+/// pointing to the article, a text submission to the HN post.
+fn insert_story(conn: &Connection, story: Story) -> Result<()> {
+    let lang = story.lang();
+    let Story {
+        id,
+        title,
+        kind,
+        submitted_at,
+        score,
+        comments,
+        archives,
+        article,
+    } = story;
+    log::trace!("Inserting story {}", id);
+
+    let (url, domain) = match kind {
+        StoryKind::Url(url) => {
+            let domain = domain::registrable(&url);
+            (url, domain)
+        }
+        StoryKind::Text(_) => (hn::submission_url(id), None),
+    };
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    // snapshots are looked for before insertion
+    let archive_checked_at = created_at as i64;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO stories \
+        (id, title, url, canonical_url, domain, archive_checked_at, \
+        created_at, submitted_at, lang, score, comments) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    // sqlite doesn't support unsigned ints
+    stmt.execute(params![
+        id,
+        title,
+        url,
+        canonical::url(&url),
+        domain,
+        archive_checked_at,
+        created_at as i64,
+        submitted_at,
+        lang,
+        score,
+        comments
+    ])?;
+
+    for archive in &archives {
+        update_archive(conn, id, archive)?;
+    }
+
+    if let Some(article) = &article {
+        insert_article(conn, id, article)?;
+    }
+
+    let text = article.as_ref().map(|article| article.text.as_str());
+    conn.execute(
+        "INSERT INTO story_search (rowid, title, text) VALUES (?1, ?2, ?3)",
+        params![id, title, text.unwrap_or_default()],
+    )?;
+
+    Ok(())
+}
+
+fn insert_article(
+    conn: &Connection,
+    story_id: StoryId,
+    article: &Article,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO story_articles \
+        (story_id, title, description, lang, word_count, text) \
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            story_id,
+            article.title,
+            article.description,
+            article.lang,
+            article.word_count as i64,
+            article.text
+        ],
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use {super::*, crate::date::SECS_IN_DAY};
+
+    pub fn test_conn() -> Result<Connection> {
+        let conn = Connection::open_in_memory()?;
+        create_tables(&conn)?;
+        Ok(conn)
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec)]
+    fn it_returns_only_new_stories() -> Result<()> {
+        let conn = test_conn()?;
+
+        assert_eq!(vec![1, 2, 3], only_new_stories(&conn, &vec![1, 2, 3])?);
+
+        let story1 = Story::random_url();
+        let story1_id = story1.id;
+        insert_story(&conn, story1)?;
+
+        let story2 = Story::random_url();
+        let story2_id = story2.id;
+        insert_story(&conn, story2)?;
+
+        assert_eq!(
+            vec![1],
+            only_new_stories(&conn, &vec![1, story1_id, story2_id])?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_adds_missing_column() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        // as created by an older version
+        conn.execute(
+            "CREATE TABLE stories (id INTEGER PRIMARY KEY, title TEXT)",
+            [],
+        )?;
+
+        assert!(add_column_if_missing(
+            &conn,
+            "stories",
+            "submitted_at",
+            "INTEGER"
+        )?);
+        // second time it's a noop
+        assert!(!add_column_if_missing(
+            &conn,
+            "stories",
+            "submitted_at",
+            "INTEGER"
+        )?);
+        conn.execute(
+            "INSERT INTO stories (id, title, submitted_at) VALUES (1, 'a', 2)",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_migrates_archive_links() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        // as created by an older version
+        conn.execute_batch(
+            "CREATE TABLE stories (
+                id INTEGER PRIMARY KEY, title TEXT NOT NULL,
+                url TEXT NOT NULL, archive_url TEXT, created_at INTEGER(4)
+            );
+            INSERT INTO stories (id, title, url, archive_url)
+                VALUES (1, 'a', 'https://a.com', 'https://archive.org/a');
+            INSERT INTO stories (id, title, url) VALUES (2, 'b', 'https://b');",
+        )?;
+        create_tables(&conn)?;
+        insert_filters(&conn, &[(1, vec![])])?;
+
+        let story = select_story(&conn, 1)?.unwrap();
+        assert_eq!(1, story.archives.len());
+        assert_eq!("wayback", story.archives[0].provider);
+        assert_eq!("https://archive.org/a", story.archives[0].url);
+        assert!(select_archives(&conn, 2)?.is_empty());
+
+        assert_eq!(Some("a.com"), story.domain.as_deref());
+
+        // creating the tables again doesn't duplicate the links nor look for
+        // domains again
+        conn.execute("UPDATE stories SET domain = NULL WHERE id = 1", [])?;
+        create_tables(&conn)?;
+        assert_eq!(1, select_archives(&conn, 1)?.len());
+        assert_eq!(None, select_story(&conn, 1)?.unwrap().domain);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_selects_earlier_submissions() -> Result<()> {
+        let conn = test_conn()?;
+
+        let submission = |id: StoryId, url: &str| {
+            let mut story = Story::random_url();
+            story.id = id;
+            story.kind = StoryKind::Url(url.to_string());
+            story.submitted_at = Some(id * SECS_IN_DAY);
+            (story, vec![])
+        };
+        insert_test_data(
+            &conn,
+            &[
+                submission(1, "http://www.example.com/a?utm_source=x"),
+                submission(2, "https://example.com/b"),
+                submission(3, "https://example.com/a/"),
+                submission(4, "https://example.com/a#comments"),
+            ],
+        )?;
+
+        let story = select_story(&conn, 4)?.unwrap();
+        assert_eq!("example.com/a", story.canonical_url);
+        assert_eq!(
+            vec![3, 1],
+            story
+                .earlier_submissions
+                .iter()
+                .map(|submission| submission.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some("1970-01-04"),
+            story.earlier_submissions[0].submitted_on.as_deref()
+        );
+        assert_eq!(
+            "https://news.ycombinator.com/item?id=3",
+            story.earlier_submissions[0].url
+        );
+
+        assert!(select_story(&conn, 1)?
+            .unwrap()
+            .earlier_submissions
+            .is_empty());
+        assert!(select_story(&conn, 2)?
+            .unwrap()
+            .earlier_submissions
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_backfills_canonical_urls() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        // as created by an older version
+        conn.execute_batch(
+            "CREATE TABLE stories (
+                id INTEGER PRIMARY KEY, title TEXT NOT NULL,
+                url TEXT NOT NULL, created_at INTEGER(4)
+            );
+            INSERT INTO stories (id, title, url)
+                VALUES (1, 'a', 'https://www.example.com/a/');",
+        )?;
+        create_tables(&conn)?;
+
+        let canonical_url: String = conn.query_row(
+            "SELECT canonical_url FROM stories WHERE id = 1",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!("example.com/a", canonical_url);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_indexes_stored_stories_for_search() -> Result<()> {
+        let conn = Connection::open_in_memory()?;
+        // as created by an older version
+        conn.execute_batch(
+            "CREATE TABLE stories (
+                id INTEGER PRIMARY KEY, title TEXT NOT NULL,
+                url TEXT NOT NULL, created_at INTEGER(4)
+            );
+            INSERT INTO stories (id, title, url, created_at)
+                VALUES (1, 'Old story', 'https://example.com/a', 1);",
+        )?;
+        create_tables(&conn)?;
+        // creating the tables again doesn't index the story twice
+        create_tables(&conn)?;
+
+        let mut story = Story::random_url();
+        story.title = "New story".to_string();
+        insert_test_data(&conn, &[(story.clone(), vec![])])?;
+
+        let found = search(&conn, "story", 10)?;
+        assert_eq!(2, found.len());
+        let old = found.iter().find(|found| found.id == 1).unwrap();
+        assert_eq!(Some("Old [story]"), old.snippet.as_deref());
+        assert_eq!(1, search(&conn, "old", 10)?.len());
+
+        let index = select_search_index(&conn)?;
+        assert_eq!(
+            vec![story.id, 1],
+            index.iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert!(index.iter().all(|story| story.snippet.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_uploads() -> Result<()> {
+        let conn = test_conn()?;
+        assert_eq!(None, select_upload_story_count(&conn, "search.json")?);
+
+        insert_test_data(&conn, &[(Story::random_url(), vec![])])?;
+        let count = count_stories(&conn)?;
+        assert_eq!(1, count);
+        insert_upload(&conn, "search.json", count)?;
+        assert_eq!(Some(1), select_upload_story_count(&conn, "search.json")?);
+
+        insert_upload(&conn, "search.json", 2)?;
+        assert_eq!(Some(2), select_upload_story_count(&conn, "search.json")?);
+        assert_eq!(None, select_upload_story_count(&conn, "other.json")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_selects_stories_by_domain() -> Result<()> {
+        let conn = test_conn()?;
+
+        let submission = |id: StoryId, url: &str| {
+            let mut story = Story::random_url();
+            story.id = id;
+            story.kind = StoryKind::Url(url.to_string());
+            (story, vec![])
+        };
+        insert_test_data(
+            &conn,
+            &[
+                submission(1, "https://blog.example.com/a"),
+                submission(2, "https://other.org/b"),
+                submission(3, "https://www.example.com/c"),
+                (Story::random_text(), vec![]),
+            ],
+        )?;
+
+        assert_eq!(
+            Some("example.com"),
+            select_story(&conn, 3)?.unwrap().domain.as_deref()
+        );
+        assert_eq!(
+            vec![3, 1],
+            select_stories_from_domain(&conn, "example.com", 5)?
+        );
+        assert_eq!(
+            vec![3],
+            select_stories_from_domain(&conn, "example.com", 1)?
+        );
+        assert_eq!(vec!["example.com"], select_frequent_domains(&conn, 2)?);
+        assert_eq!(
+            vec!["example.com", "other.org"],
+            select_frequent_domains(&conn, 1)?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_selects_story() -> Result<()> {
+        let conn = test_conn()?;
+
+        let stories = &[
+            (
+                Story::random_url(),
+                vec![FilterKind::AskHn, FilterKind::ShowHn],
+            ),
+            (Story::random_url(), vec![FilterKind::LargeNewspaper]),
+            (Story::random_url(), vec![]),
+        ];
+        insert_test_data(&conn, stories)?;
+
+        // for each story, gets it from db and asserts it equals the test data
+        for (story, filters) in stories {
+            let db_story = select_story(&conn, story.id)?.unwrap();
+
+            assert_eq!(story.id, db_story.id);
+            assert_eq!(&story.title, &db_story.title);
+
+            assert_eq!(filters.len(), db_story.filters.len());
+            assert_eq!(filters.len(), db_story.filter_reasons.len());
+            for filter in filters {
+                assert!(db_story.filters.contains(filter));
+                assert_eq!(
+                    Some(&test_reason(*filter)),
+                    db_story.filter_reasons.get(filter)
+                );
+            }
+
+            let versions: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM story_filter_versions \
+                WHERE story_id = ?1",
+                params![story.id],
+                |row| row.get(0),
+            )?;
+            assert_eq!(filters.len() as i64, versions);
+        }
+
+        let version = FilterKind::LargeNewspaper.version();
+        assert_eq!(
+            vec![(Some(version), 1)],
+            select_flag_versions(&conn, FilterKind::LargeNewspaper)?
+        );
+        assert!(select_flag_versions(&conn, FilterKind::BigTech)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_first_run_with_filter_versions() -> Result<()> {
+        let conn = test_conn()?;
+        let version = FilterKind::BigTech.version();
+        conn.execute(
+            "INSERT INTO filter_versions (filter, version, deployed_at) \
+                VALUES ('amfg', ?1, 1), ('removed', 1, 1)",
+            params![version],
+        )?;
+
+        insert_filter_versions(&conn)?;
+        let versions = select_filter_versions(&conn)?;
+
+        // already recorded versions keep the time of the first run
+        assert_eq!(Some(&1), versions.get(&(FilterKind::BigTech, version)));
+        assert!(versions.contains_key(&(FilterKind::Flamewar, 1)));
+        assert_eq!(
+            filter::FILTERS.len() + filter::DYNAMIC_FILTERS.len(),
+            versions.len()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_updates_scores() -> Result<()> {
+        let conn = test_conn()?;
+
+        let mut story = Story::random_url();
+        story.submitted_at = Some(1609459200);
+        story.score = Some(10);
+        story.comments = Some(2);
+        insert_test_data(&conn, &[(story.clone(), vec![])])?;
+
+        let db_story = select_story(&conn, story.id)?.unwrap();
+        assert_eq!(Some(1609459200), db_story.submitted_at);
+        assert_eq!(Some(10), db_story.score);
+        assert_eq!(Some(2), db_story.comments);
+
+        story.score = Some(150);
+        story.comments = Some(80);
+        // stories we don't store are ignored
+        update_scores(&conn, &[story.clone(), Story::random_url()])?;
+
+        let db_story = select_story(&conn, story.id)?.unwrap();
+        assert_eq!(Some(150), db_story.score);
+        assert_eq!(Some(80), db_story.comments);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_stores_articles() -> Result<()> {
+        let conn = test_conn()?;
+
+        let article = Article {
+            title: Some("Title".to_string()),
+            description: None,
+            lang: Some("en".to_string()),
+            word_count: 3,
+            text: "Some readable text".to_string(),
+            paywall_marker: None,
+        };
+        let mut story = Story::random_url();
+        story.article = Some(article.clone());
+        let without_article = Story::random_url();
+        insert_test_data(
+            &conn,
+            &[(story.clone(), vec![]), (without_article.clone(), vec![])],
+        )?;
+
+        let stories = select_stories_with_articles(&conn)?;
+        assert_eq!(2, stories.len());
+        for (stored, stored_article) in stories {
+            if stored.id == story.id {
+                assert_eq!(Some(&article), stored_article.as_ref());
+            } else {
+                assert_eq!(without_article.id, stored.id);
+                assert_eq!(None, stored_article);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts given stories + filters to the database.
+    #[allow(clippy::needless_borrow)]
+    pub fn insert_test_data(
         conn: &Connection,
         stories: &[(Story, Vec<FilterKind>)],
     ) -> Result<()> {
@@ -339,10 +1869,20 @@ pub mod tests {
             &conn,
             &stories
                 .iter()
-                .map(|(story, filters)| (story.id, filters.clone()))
+                .map(|(story, filters)| {
+                    let filters = filters
+                        .iter()
+                        .map(|filter| (*filter, test_reason(*filter)))
+                        .collect();
+                    (story.id, filters)
+                })
                 .collect::<Vec<_>>(),
         )?;
 
         Ok(())
     }
+
+    pub fn test_reason(filter: FilterKind) -> String {
+        format!("flagged by {}", filter)
+    }
 }
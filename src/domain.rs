@@ -0,0 +1,104 @@
+//! Like HN, we show the site next to the story title. The site is the
+//! registrable domain of the story url, i.e. the public suffix plus one more
+//! label: `blog.example.co.uk` becomes `example.co.uk`.
+//!
+//! We don't ship the whole [public suffix list][psl]. Most domains submitted to
+//! HN have a single label suffix, and we list the common multi label ones.
+//!
+//! [psl]: https://publicsuffix.org
+
+use {reqwest::Url, std::net::IpAddr};
+
+// Public suffixes with more than one label. Includes hosting platforms whose
+// subdomains belong to different people.
+const MULTI_LABEL_SUFFIXES: &[&str] = &[
+    "ac.uk",
+    "blogspot.com",
+    "co.in",
+    "co.jp",
+    "co.kr",
+    "co.nz",
+    "co.uk",
+    "co.za",
+    "com.au",
+    "com.br",
+    "com.cn",
+    "com.hk",
+    "com.mx",
+    "com.sg",
+    "com.tr",
+    "com.tw",
+    "edu.au",
+    "github.io",
+    "gitlab.io",
+    "gov.au",
+    "gov.uk",
+    "herokuapp.com",
+    "ne.jp",
+    "net.au",
+    "netlify.app",
+    "or.jp",
+    "org.au",
+    "org.nz",
+    "org.uk",
+    "pages.dev",
+    "vercel.app",
+];
+
+/// Returns the registrable domain of given url, or the address if the host is
+/// an IP. Returns [`None`] if the url has no host.
+pub fn registrable(url: &str) -> Option<String> {
+    let parsed = Url::parse(url.trim()).ok()?;
+    // lowercased by the parser
+    let host = parsed.host_str()?;
+
+    let is_ip = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok();
+    if is_ip {
+        return Some(host.to_string());
+    }
+
+    let labels: Vec<_> = host.split('.').filter(|l| !l.is_empty()).collect();
+    let suffix_len = if labels.len() > 2
+        && MULTI_LABEL_SUFFIXES
+            .contains(&labels[labels.len() - 2..].join(".").as_str())
+    {
+        2
+    } else {
+        1
+    };
+    let domain_len = (suffix_len + 1).min(labels.len());
+
+    Some(labels[labels.len() - domain_len..].join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_extracts_registrable_domain() {
+        let cases = [
+            ("https://example.com", "example.com"),
+            ("https://www.example.com/article", "example.com"),
+            ("http://blog.Example.com:8080/a?b=c", "example.com"),
+            ("https://news.bbc.co.uk/a", "bbc.co.uk"),
+            ("https://www.bbc.co.uk", "bbc.co.uk"),
+            ("https://porkbrain.github.io/post", "porkbrain.github.io"),
+            ("https://github.com/bausano/suckless.hn", "github.com"),
+            ("https://localhost/a", "localhost"),
+            ("http://127.0.0.1:8000/a", "127.0.0.1"),
+            ("http://[::1]/a", "[::1]"),
+            ("https://example.com./a", "example.com"),
+        ];
+        for (url, domain) in &cases {
+            assert_eq!(Some(domain.to_string()), registrable(url), "{}", url);
+        }
+
+        assert_eq!(None, registrable("not a url"));
+        assert_eq!(None, registrable("mailto:someone@example.com"));
+    }
+}
@@ -0,0 +1,257 @@
+//! Before changing a filter we want to know what it does to the stories we've
+//! already flagged. A candidate filter is run over all stored stories and its
+//! verdicts are compared with the flags in the database.
+//!
+//! The candidate is either the current implementation of a built-in filter,
+//! which is useful when the stored flags were computed by an older version, or
+//! a list of keywords given on the command line to try out before writing any
+//! code.
+
+use {rusqlite::Connection, std::fmt};
+
+use {
+    super::{
+        keywords::{Keyword, KeywordGroups},
+        *,
+    },
+    crate::{db, hn},
+};
+
+/// Title based filter built from keywords given on the command line.
+pub struct Keywords {
+    name: &'static str,
+    groups: KeywordGroups,
+}
+
+/// How would the stored flags of a filter change if we replaced it with the
+/// candidate.
+pub struct Report {
+    pub filter: FilterKind,
+    /// How many stories we ran the candidate on.
+    pub evaluated: usize,
+    /// How many stories are flagged in the database.
+    pub flagged_before: usize,
+    /// How many of the stored flags each version of the filter made, the
+    /// oldest first. `None` for flags made before we recorded versions.
+    pub flagged_by_version: Vec<(Option<u32>, usize)>,
+    /// How many stories the candidate flags.
+    pub flagged_after: usize,
+    /// Stories which the candidate flags but the database doesn't, with the
+    /// reason given by the candidate.
+    pub newly_flagged: Vec<(StoryWithFilters, String)>,
+    /// Stories flagged in the database which the candidate doesn't flag.
+    pub newly_unflagged: Vec<StoryWithFilters>,
+}
+
+impl Keywords {
+    /// Keywords are matched on word boundaries regardless of the case. The
+    /// name is that of the filter the candidate is compared with.
+    pub fn new(filter: FilterKind, keywords: &[String]) -> Self {
+        // the command runs once and exits, leaking is simpler than making
+        // the keyword matcher generic over owned strings
+        let keywords: Vec<_> = keywords
+            .iter()
+            .map(|keyword| {
+                Keyword::new(Box::leak(keyword.clone().into_boxed_str()))
+            })
+            .collect();
+        let groups = KeywordGroups::new(&[(filter.name(), &keywords)]);
+
+        Self {
+            name: filter.name(),
+            groups,
+        }
+    }
+}
+
+impl Filter for Keywords {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        let keywords: Vec<_> = self
+            .groups
+            .find(&story.title)
+            .into_iter()
+            .map(|found| format!("\"{}\"", found.keyword))
+            .collect();
+
+        if keywords.is_empty() {
+            None
+        } else {
+            Some(format!("mentions {}", keywords.join(", ")))
+        }
+    }
+}
+
+/// Runs the candidate on all stored stories and compares its verdicts with the
+/// stored flags of the filter. Without keywords the candidate is the current
+/// implementation of the filter.
+pub fn evaluate(
+    conn: &Connection,
+    filter: FilterKind,
+    keywords: Option<&Keywords>,
+) -> Result<Report> {
+    let mut report = Report {
+        filter,
+        evaluated: 0,
+        flagged_before: 0,
+        flagged_by_version: db::select_flag_versions(conn, filter)?,
+        flagged_after: 0,
+        newly_flagged: vec![],
+        newly_unflagged: vec![],
+    };
+
+    let (stored, stories): (Vec<_>, Vec<_>) =
+        db::select_stories_with_articles(conn)?
+            .into_iter()
+            .map(|(stored, article)| {
+                let mut story = as_story(&stored);
+                story.article = article;
+                (stored, story)
+            })
+            .unzip();
+
+    let reasons: Vec<_> = match keywords {
+        Some(keywords) => {
+            stories.iter().map(|story| keywords.reason(story)).collect()
+        }
+        None => for_stories(&stories)
+            .into_iter()
+            .map(|(_, story_filters)| {
+                story_filters
+                    .into_iter()
+                    .find(|(f, _)| *f == filter)
+                    .map(|(_, reason)| reason)
+            })
+            .collect(),
+    };
+
+    for (stored, reason) in stored.into_iter().zip(reasons) {
+        let was_flagged = stored.filters.contains(&filter);
+
+        report.evaluated += 1;
+        if was_flagged {
+            report.flagged_before += 1;
+        }
+        match reason {
+            Some(reason) => {
+                report.flagged_after += 1;
+                if !was_flagged {
+                    report.newly_flagged.push((stored, reason));
+                }
+            }
+            None if was_flagged => report.newly_unflagged.push(stored),
+            None => (),
+        }
+    }
+
+    Ok(report)
+}
+
+// Filters work with stories as fetched from HN, we have to convert the stored
+// data back. Text submissions link to HN and their text isn't stored. The
+// article is selected separately.
+pub(super) fn as_story(stored: &StoryWithFilters) -> Story {
+    let hn_submission_url = hn::submission_url(stored.id);
+    let kind = if stored.domain.is_none() && stored.url == hn_submission_url {
+        StoryKind::Text(String::new())
+    } else {
+        StoryKind::Url(stored.url.clone())
+    };
+
+    Story {
+        id: stored.id,
+        title: stored.title.clone(),
+        submitted_at: stored.submitted_at,
+        score: stored.score,
+        comments: stored.comments,
+        archives: vec![],
+        article: None,
+        kind,
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Filter {}", self.filter)?;
+        writeln!(f, "  evaluated stories: {}", self.evaluated)?;
+        writeln!(f, "  flagged in db:     {}", self.flagged_before)?;
+        let versions: Vec<_> = self
+            .flagged_by_version
+            .iter()
+            .map(|(version, count)| match version {
+                Some(version) => format!("v{}: {}", version, count),
+                None => format!("unknown: {}", count),
+            })
+            .collect();
+        writeln!(
+            f,
+            "    by version:      {} (current v{})",
+            versions.join(", "),
+            self.filter.version()
+        )?;
+        writeln!(f, "  flagged now:       {}", self.flagged_after)?;
+
+        writeln!(f, "\nNewly flagged ({}):", self.newly_flagged.len())?;
+        for (story, reason) in &self.newly_flagged {
+            writeln!(f, "  [{}] {} ({})", story.id, story.title, reason)?;
+        }
+
+        writeln!(f, "\nNewly unflagged ({}):", self.newly_unflagged.len())?;
+        for story in &self.newly_unflagged {
+            writeln!(f, "  [{}] {}", story.id, story.title)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_evaluates_candidate_against_stored_flags() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+
+        let titled = |title: &str, filters: Vec<FilterKind>| {
+            let mut story = Story::random_url();
+            story.title = title.to_string();
+            (story, filters)
+        };
+        let stories = [
+            // flagged by an older version of the filter
+            titled("Pineapple pizza", vec![FilterKind::BigTech]),
+            titled("Google is down", vec![FilterKind::BigTech]),
+            // not flagged by an older version of the filter
+            titled("WhatsApp is down", vec![]),
+            titled("Rust 2.0 released", vec![]),
+        ];
+        db::tests::insert_test_data(&conn, &stories)?;
+
+        let report = evaluate(&conn, FilterKind::BigTech, None)?;
+        assert_eq!(4, report.evaluated);
+        assert_eq!(2, report.flagged_before);
+        let version = FilterKind::BigTech.version();
+        assert_eq!(vec![(Some(version), 2)], report.flagged_by_version);
+        assert!(report
+            .to_string()
+            .contains(&format!("by version:      v{}: 2", version)));
+        assert_eq!(2, report.flagged_after);
+        assert_eq!(1, report.newly_flagged.len());
+        assert_eq!("WhatsApp is down", report.newly_flagged[0].0.title);
+        assert_eq!(1, report.newly_unflagged.len());
+        assert_eq!("Pineapple pizza", report.newly_unflagged[0].title);
+
+        let candidate =
+            Keywords::new(FilterKind::BigTech, &["rust".to_string()]);
+        let report = evaluate(&conn, FilterKind::BigTech, Some(&candidate))?;
+        assert_eq!(1, report.flagged_after);
+        assert_eq!("mentions \"rust\"", report.newly_flagged[0].1);
+        assert_eq!(2, report.newly_unflagged.len());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Runs all filters over a corpus of stories with known flags, see
+//! `golden.tsv`. A filter change which flags or unflags any story in the
+//! corpus fails the test with a list of the affected stories.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use super::*;
+
+const CORPUS: &str = include_str!("golden.tsv");
+
+// The corpus stories are submitted at a fixed time, otherwise e.g. the old
+// content filter would flag them differently as years go by.
+const SUBMITTED_AT: i64 = 1690848000; // 2023-08-01
+
+struct GoldenStory {
+    line: usize,
+    expected: BTreeSet<&'static str>,
+    /// Filters which should flag the story but don't yet.
+    known_misses: BTreeSet<&'static str>,
+    story: Story,
+}
+
+#[test]
+fn it_flags_golden_corpus() {
+    let corpus = parse_corpus();
+    assert!(corpus.len() > 300, "Corpus is too small");
+
+    // each filter must be exercised by the corpus, except for the topic
+    // filters which flag nothing without a trained model
+    for filter in FILTERS.iter().filter(|f| !topic::TOPICS.contains(f)) {
+        assert!(
+            corpus
+                .iter()
+                .any(|golden| golden.expected.contains(&filter.name())),
+            "Corpus doesn't flag anything with {}",
+            filter
+        );
+    }
+
+    let stories: Vec<_> =
+        corpus.iter().map(|golden| golden.story.clone()).collect();
+    let mut diff = String::new();
+    for (golden, (_, flags)) in corpus.iter().zip(for_stories(&stories)) {
+        let actual: BTreeSet<_> = flags.iter().map(|(f, _)| f.name()).collect();
+        if actual == golden.expected {
+            continue;
+        }
+
+        writeln!(diff, "line {}: {}", golden.line, golden.story.title).unwrap();
+        for name in golden.expected.difference(&actual) {
+            writeln!(diff, "  - {}", name).unwrap();
+        }
+        for (filter, reason) in flags
+            .iter()
+            .filter(|(f, _)| !golden.expected.contains(&f.name()))
+        {
+            let note = if golden.known_misses.contains(&filter.name()) {
+                ", no longer a known miss"
+            } else {
+                ""
+            };
+            writeln!(diff, "  + {} ({}){}", filter, reason, note).unwrap();
+        }
+    }
+
+    assert!(
+        diff.is_empty(),
+        "Filters disagree with the golden corpus \
+        (- expected but not flagged, + flagged but not expected):\n{}",
+        diff
+    );
+}
+
+fn parse_corpus() -> Vec<GoldenStory> {
+    CORPUS
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            let line_number = index + 1;
+            let columns: Vec<_> = line.split('\t').collect();
+            assert!(
+                columns.len() == 3 || columns.len() == 4,
+                "Line {} must have 3 or 4 columns",
+                line_number
+            );
+
+            let flags: Vec<_> = match columns[0] {
+                "-" => vec![],
+                flags => flags.split(',').collect(),
+            };
+            let expected: BTreeSet<_> = flags
+                .iter()
+                .copied()
+                .filter(|name| !name.starts_with('~'))
+                .collect();
+            let known_misses: BTreeSet<_> = flags
+                .iter()
+                .filter_map(|name| name.strip_prefix('~'))
+                .collect();
+            for name in expected.iter().chain(&known_misses) {
+                assert!(
+                    by_name(name).is_some(),
+                    "Unknown filter {} on line {}",
+                    name,
+                    line_number
+                );
+            }
+
+            let mut story = Story::random_url();
+            story.title = columns[2].to_string();
+            story.submitted_at = Some(SUBMITTED_AT);
+            story.kind = match columns[1] {
+                "-" => StoryKind::Text(String::new()),
+                url => StoryKind::Url(url.to_string()),
+            };
+
+            if let Some(words) = columns.get(3) {
+                story.article = Some(Article {
+                    word_count: words.parse().unwrap_or_else(|_| {
+                        panic!("Invalid word count on line {}", line_number)
+                    }),
+                    ..Default::default()
+                });
+            }
+
+            GoldenStory {
+                line: line_number,
+                expected,
+                known_misses,
+                story,
+            }
+        })
+        .collect()
+}
@@ -1,17 +1,60 @@
-use super::*;
+use {
+    super::{
+        keywords::{Keyword, KeywordGroups, KeywordMatch},
+        *,
+    },
+    crate::{date, lang},
+    lazy_static::lazy_static,
+    regex::Regex,
+    reqwest::Url,
+};
 
 pub struct AskHn;
 pub struct ShowHn;
+pub struct TellHn;
+pub struct LaunchHn;
+pub struct Hiring;
 pub struct LargeNewspaper;
 pub struct BigTech;
+pub struct OldContent;
+pub struct Pdf;
+pub struct Video;
+pub struct Audio;
+pub struct Paywall;
+pub struct Flamewar;
+pub struct LowSignal;
+pub struct LongRead;
+pub struct NonEnglish;
+pub struct AiHype;
+pub struct Crypto;
+pub struct Politics;
+
+/// Stories of a media type are recognized by a tag in the title, e.g. "[pdf]",
+/// by the extension of the linked file or by the site which hosts the media.
+struct Media {
+    /// Lowercase tags without the brackets.
+    title_tags: &'static [&'static str],
+    /// Lowercase extensions without the dot.
+    extensions: &'static [&'static str],
+    /// Registrable domains.
+    domains: &'static [&'static str],
+}
 
 impl Filter for AskHn {
     fn name(&self) -> &'static str {
         "askhn"
     }
 
-    fn should_flag(&self, story: &Story) -> bool {
-        story.title.starts_with("Ask HN")
+    fn changelog(&self) -> &'static [Change] {
+        &[Change {
+            day: "2026-10-18",
+            description: "The prefix matches regardless of the case and \
+            spacing, e.g. \"ask hn :\".",
+        }]
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        hn_prefix(story, "Ask")
     }
 }
 
@@ -20,8 +63,67 @@ impl Filter for ShowHn {
         "showhn"
     }
 
-    fn should_flag(&self, story: &Story) -> bool {
-        story.title.starts_with("Show HN")
+    fn changelog(&self) -> &'static [Change] {
+        &[Change {
+            day: "2026-10-18",
+            description: "The prefix matches regardless of the case and \
+            spacing, e.g. \"show hn :\".",
+        }]
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        hn_prefix(story, "Show")
+    }
+}
+
+impl Filter for TellHn {
+    fn name(&self) -> &'static str {
+        "tellhn"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        hn_prefix(story, "Tell")
+    }
+}
+
+/// Launches of YC companies.
+impl Filter for LaunchHn {
+    fn name(&self) -> &'static str {
+        "launchhn"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        hn_prefix(story, "Launch")
+    }
+}
+
+lazy_static! {
+    // The monthly threads started by the whoishiring account.
+    static ref HIRING_THREAD: Regex = Regex::new(concat!(
+        r"(?i)^\s*ask\s*hn\s*:?\s*",
+        r"(who is hiring|who wants to be hired|freelancer\?)"
+    ))
+    .unwrap();
+
+    // Job ads of YC companies, e.g. "Stripe (YC S09) is hiring".
+    static ref YC_JOB_AD: Regex =
+        Regex::new(r"(?i)\(yc [sw]\d{2}\)\s+(is\s+)?hiring").unwrap();
+}
+
+/// Hiring threads and job ads.
+impl Filter for Hiring {
+    fn name(&self) -> &'static str {
+        "hiring"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        if HIRING_THREAD.is_match(&story.title) {
+            Some("monthly hiring thread".to_string())
+        } else if YC_JOB_AD.is_match(&story.title) {
+            Some("job ad of a YC company".to_string())
+        } else {
+            None
+        }
     }
 }
 
@@ -30,32 +132,59 @@ impl Filter for LargeNewspaper {
         "bignews"
     }
 
-    fn should_flag(&self, story: &Story) -> bool {
-        lazy_static! {
-            static ref NEWSPAPER_WEBSITE: Regex = Regex::new(concat!(
-                "https?://", // doesn't have to be tls
-                "(?:www\\.)?", // can start with www
-                "(?:", // start non-capturing group of websites
-                "bbc\\.co\\.uk|",
-                "bbc\\.com|",
-                "bloomberg\\.com|",
-                "cnbc\\.com|",
-                "forbes\\.com|",
-                "latimes\\.com|",
-                "nytimes\\.com|",
-                "spectator\\.co\\.uk|",
-                "theguardian\\.com|",
-                "thehill\\.com|",
-                "vice\\.com|",
-                "wsj\\.com",
-                ")"
-            )).expect("Invalid newspaper website regex");
-        }
+    fn reason(&self, story: &Story) -> Option<String> {
+        // registrable domains, subdomains such as "www.bbc.co.uk" match too
+        const NEWSPAPER_DOMAINS: &[&str] = &[
+            "bbc.co.uk",
+            "bbc.com",
+            "bloomberg.com",
+            "cnbc.com",
+            "forbes.com",
+            "latimes.com",
+            "nytimes.com",
+            "spectator.co.uk",
+            "theguardian.com",
+            "thehill.com",
+            "vice.com",
+            "wsj.com",
+        ];
 
-        match &story.kind {
-            StoryKind::Url(url) => NEWSPAPER_WEBSITE.is_match(url),
-            StoryKind::Text(_) => false,
-        }
+        story
+            .domain()
+            .filter(|domain| NEWSPAPER_DOMAINS.contains(&domain.as_str()))
+            .map(|domain| format!("links to {}", domain))
+    }
+}
+
+lazy_static! {
+    static ref BIG_TECH_KEYWORDS: KeywordGroups = KeywordGroups::new(&[
+        ("amazon", &[Keyword::company("Amazon"), Keyword::new("AWS")]),
+        ("apple", &[Keyword::company("Apple")]),
+        (
+            "google",
+            &[
+                Keyword::new("Google"),
+                Keyword::company("Alphabet"),
+                Keyword::new("YouTube"),
+            ],
+        ),
+        (
+            "meta",
+            &[
+                Keyword::new("Facebook"),
+                Keyword::company("Meta"),
+                Keyword::new("WhatsApp"),
+                Keyword::new("Instagram"),
+            ],
+        ),
+        ("microsoft", &[Keyword::new("Microsoft")]),
+    ]);
+}
+
+impl BigTech {
+    /// Which companies and by which keywords are mentioned in the title.
+    pub fn matches(&self, story: &Story) -> Vec<KeywordMatch> {
+        BIG_TECH_KEYWORDS.find(&story.title)
     }
 }
 
@@ -64,12 +193,357 @@ impl Filter for BigTech {
         "amfg"
     }
 
-    fn should_flag(&self, story: &Story) -> bool {
-        let t = &story.title;
-        t.contains("Apple")
-            || t.contains("Microsoft")
-            || t.contains("Facebook")
-            || t.contains("Google")
+    fn changelog(&self) -> &'static [Change] {
+        &[Change {
+            day: "2026-10-18",
+            description: "Only whole words match regardless of the case, \
+            e.g. \"Pineapple\" no longer matches Apple. Alphabet, YouTube, \
+            Meta, WhatsApp, Instagram, Amazon and AWS are now included. \
+            Apple, Amazon, Alphabet and Meta only match where the title \
+            refers to the company, not in e.g. \"the Big Apple\".",
+        }]
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        let matches = self.matches(story);
+        if matches.is_empty() {
+            return None;
+        }
+
+        let mentions: Vec<_> = matches
+            .iter()
+            .map(|KeywordMatch { group, keyword }| {
+                format!("{} (\"{}\")", group, keyword)
+            })
+            .collect();
+        Some(format!("mentions {}", mentions.join(", ")))
+    }
+}
+
+lazy_static! {
+    // HN moderators append the year of publication to titles of old content,
+    // e.g. "(2014)". Sometimes it's a decade, e.g. "(1990s)", or square
+    // brackets are used.
+    static ref TITLE_YEAR: Regex =
+        Regex::new(r"[(\[](1[89]\d\d|20\d\d)(s?)[)\]]").unwrap();
+}
+
+impl OldContent {
+    /// The latest year in brackets in the title. A decade counts as its last
+    /// year, "(1990s)" is 1999.
+    fn title_year(title: &str) -> Option<i64> {
+        TITLE_YEAR
+            .captures_iter(title)
+            .filter_map(|captures| {
+                let year: i64 = captures[1].parse().ok()?;
+                let is_decade = !captures[2].is_empty();
+                Some(if is_decade { year + 9 } else { year })
+            })
+            .max()
+    }
+}
+
+impl Filter for OldContent {
+    fn name(&self) -> &'static str {
+        "old"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        // a year in the title of e.g. "Ask HN" is not when it was published
+        if let StoryKind::Text(_) = story.kind {
+            return None;
+        }
+
+        let year = Self::title_year(&story.title)?;
+        let submitted_in =
+            date::year(story.submitted_at.unwrap_or_else(date::now));
+
+        // the content might have been published on the last day of the year,
+        // only the full years since then count, i.e. "(2025)" isn't old on
+        // 2026-01-02
+        let full_years = submitted_in - year - 1;
+        if full_years < settings().old_content_min_age_years {
+            return None;
+        }
+
+        let age = submitted_in - year;
+        let unit = if age == 1 { "year" } else { "years" };
+        Some(format!("published in {}, {} {} before", year, age, unit))
+    }
+}
+
+impl Media {
+    fn reason(&self, story: &Story) -> Option<String> {
+        let title = story.title.to_lowercase();
+        let tag = self
+            .title_tags
+            .iter()
+            .find(|tag| title.contains(&format!("[{}]", tag)));
+        if let Some(tag) = tag {
+            return Some(format!("title tagged [{}]", tag));
+        }
+
+        let url = match &story.kind {
+            StoryKind::Url(url) => url,
+            StoryKind::Text(_) => return None,
+        };
+
+        let path = Url::parse(url).ok()?.path().to_lowercase();
+        let extension = self
+            .extensions
+            .iter()
+            .find(|extension| path.ends_with(&format!(".{}", extension)));
+        if let Some(extension) = extension {
+            return Some(format!("links to a .{} file", extension));
+        }
+
+        story
+            .domain()
+            .filter(|domain| self.domains.contains(&domain.as_str()))
+            .map(|domain| format!("links to {}", domain))
+    }
+}
+
+impl Filter for Pdf {
+    fn name(&self) -> &'static str {
+        "pdf"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        Media {
+            title_tags: &["pdf"],
+            extensions: &["pdf"],
+            domains: &[],
+        }
+        .reason(story)
+    }
+}
+
+impl Filter for Video {
+    fn name(&self) -> &'static str {
+        "video"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        Media {
+            title_tags: &["video"],
+            extensions: &["mkv", "mov", "mp4", "webm"],
+            domains: &[
+                "dailymotion.com",
+                "ted.com",
+                "twitch.tv",
+                "vimeo.com",
+                "youtu.be",
+                "youtube.com",
+            ],
+        }
+        .reason(story)
+    }
+}
+
+impl Filter for Audio {
+    fn name(&self) -> &'static str {
+        "audio"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        Media {
+            title_tags: &["audio", "podcast"],
+            extensions: &["flac", "m4a", "mp3", "ogg", "wav"],
+            domains: &[
+                "anchor.fm",
+                "buzzsprout.com",
+                "libsyn.com",
+                "overcast.fm",
+                "pca.st",
+                "podbean.com",
+                "simplecast.com",
+                "soundcloud.com",
+                "transistor.fm",
+            ],
+        }
+        .reason(story)
+    }
+}
+
+impl Filter for Paywall {
+    fn name(&self) -> &'static str {
+        "paywall"
+    }
+
+    /// Only checks the domain, see [`super::paywall`] for detection by the
+    /// page content.
+    fn reason(&self, story: &Story) -> Option<String> {
+        // sites with a hard or metered paywall, more can be configured
+        const PAYWALL_DOMAINS: &[&str] = &[
+            "barrons.com",
+            "bloomberg.com",
+            "bostonglobe.com",
+            "businessinsider.com",
+            "economist.com",
+            "ft.com",
+            "hbr.org",
+            "latimes.com",
+            "newyorker.com",
+            "nytimes.com",
+            "telegraph.co.uk",
+            "theatlantic.com",
+            "theinformation.com",
+            "thetimes.co.uk",
+            "washingtonpost.com",
+            "wired.com",
+            "wsj.com",
+        ];
+
+        story
+            .domain()
+            .filter(|domain| {
+                PAYWALL_DOMAINS.contains(&domain.as_str())
+                    || settings().paywall_domains.contains(domain)
+            })
+            .map(|domain| format!("links to {}", domain))
+    }
+}
+
+// Average adult reading speed.
+const WORDS_PER_MINUTE: usize = 230;
+
+/// Articles which take a while to read. We only know the length of the pages
+/// we fetched, see [`crate::article`].
+impl Filter for LongRead {
+    fn name(&self) -> &'static str {
+        "longread"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        let words = story.article.as_ref()?.word_count;
+        if words >= settings().long_read_min_words {
+            Some(format!(
+                "{} words, about {} minutes to read",
+                words,
+                words / WORDS_PER_MINUTE
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Stories written in a language other than English, see [`crate::lang`].
+impl Filter for NonEnglish {
+    fn name(&self) -> &'static str {
+        "nonenglish"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        let code = story.lang().filter(|code| *code != lang::ENGLISH)?;
+        Some(format!("written in {}", lang::name(code).unwrap_or(code)))
+    }
+}
+
+/// AI hype, from breathless product launches to predictions of the end of
+/// all jobs. Classified by a trained model, see [`topic`].
+impl Filter for AiHype {
+    fn name(&self) -> &'static str {
+        "aihype"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        topic::reason(FilterKind::AiHype, story)
+    }
+
+    fn changelog(&self) -> &'static [Change] {
+        topic::changelog(FilterKind::AiHype)
+    }
+}
+
+/// Cryptocurrencies, NFTs and web3. Classified by a trained model, see
+/// [`topic`].
+impl Filter for Crypto {
+    fn name(&self) -> &'static str {
+        "crypto"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        topic::reason(FilterKind::Crypto, story)
+    }
+
+    fn changelog(&self) -> &'static [Change] {
+        topic::changelog(FilterKind::Crypto)
+    }
+}
+
+/// Politics and elections. Classified by a trained model, see [`topic`].
+impl Filter for Politics {
+    fn name(&self) -> &'static str {
+        "politics"
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        topic::reason(FilterKind::Politics, story)
+    }
+
+    fn changelog(&self) -> &'static [Change] {
+        topic::changelog(FilterKind::Politics)
+    }
+}
+
+/// Discussions with many more comments than points tend to be heated.
+impl Filter for Flamewar {
+    fn name(&self) -> &'static str {
+        "flamewar"
+    }
+
+    fn reason(&self, _story: &Story) -> Option<String> {
+        None
+    }
+
+    fn dynamic_reason(
+        &self,
+        story: &StoryWithFilters,
+        _now: i64,
+    ) -> Option<String> {
+        let (score, comments) = (story.score?, story.comments?);
+        let settings = settings();
+        let is_heated = comments >= settings.flamewar_min_comments
+            && comments as f64
+                > score as f64 * settings.flamewar_comments_per_point;
+
+        if is_heated {
+            Some(format!("{} comments on {} points", comments, score))
+        } else {
+            None
+        }
+    }
+}
+
+/// Stories which didn't collect many points even after a few hours. A fresh
+/// story is given a chance.
+impl Filter for LowSignal {
+    fn name(&self) -> &'static str {
+        "lowsignal"
+    }
+
+    fn reason(&self, _story: &Story) -> Option<String> {
+        None
+    }
+
+    fn dynamic_reason(
+        &self,
+        story: &StoryWithFilters,
+        now: i64,
+    ) -> Option<String> {
+        let (score, submitted_at) = (story.score?, story.submitted_at?);
+        let hours = (now - submitted_at) / 3600;
+
+        let settings = settings();
+        if hours >= settings.low_signal_after_hours
+            && score < settings.low_signal_min_score
+        {
+            Some(format!("{} points after {} hours", score, hours))
+        } else {
+            None
+        }
     }
 }
 
@@ -82,19 +556,103 @@ impl Filter for FilterKind {
             Self::ShowHn => ShowHn.name(),
             Self::LargeNewspaper => LargeNewspaper.name(),
             Self::BigTech => BigTech.name(),
+            Self::OldContent => OldContent.name(),
+            Self::Pdf => Pdf.name(),
+            Self::Video => Video.name(),
+            Self::Audio => Audio.name(),
+            Self::Paywall => Paywall.name(),
+            Self::TellHn => TellHn.name(),
+            Self::LaunchHn => LaunchHn.name(),
+            Self::Hiring => Hiring.name(),
+            Self::Flamewar => Flamewar.name(),
+            Self::LowSignal => LowSignal.name(),
+            Self::LongRead => LongRead.name(),
+            Self::NonEnglish => NonEnglish.name(),
+            Self::AiHype => AiHype.name(),
+            Self::Crypto => Crypto.name(),
+            Self::Politics => Politics.name(),
+        }
+    }
+
+    fn reason(&self, story: &Story) -> Option<String> {
+        match self {
+            Self::AskHn => AskHn.reason(story),
+            Self::ShowHn => ShowHn.reason(story),
+            Self::LargeNewspaper => LargeNewspaper.reason(story),
+            Self::BigTech => BigTech.reason(story),
+            Self::OldContent => OldContent.reason(story),
+            Self::Pdf => Pdf.reason(story),
+            Self::Video => Video.reason(story),
+            Self::Audio => Audio.reason(story),
+            Self::Paywall => Paywall.reason(story),
+            Self::TellHn => TellHn.reason(story),
+            Self::LaunchHn => LaunchHn.reason(story),
+            Self::Hiring => Hiring.reason(story),
+            Self::Flamewar => Flamewar.reason(story),
+            Self::LowSignal => LowSignal.reason(story),
+            Self::LongRead => LongRead.reason(story),
+            Self::NonEnglish => NonEnglish.reason(story),
+            Self::AiHype => AiHype.reason(story),
+            Self::Crypto => Crypto.reason(story),
+            Self::Politics => Politics.reason(story),
         }
     }
 
-    fn should_flag(&self, story: &Story) -> bool {
+    fn changelog(&self) -> &'static [Change] {
         match self {
-            Self::AskHn => AskHn.should_flag(story),
-            Self::ShowHn => ShowHn.should_flag(story),
-            Self::LargeNewspaper => LargeNewspaper.should_flag(story),
-            Self::BigTech => BigTech.should_flag(story),
+            Self::AskHn => AskHn.changelog(),
+            Self::ShowHn => ShowHn.changelog(),
+            Self::LargeNewspaper => LargeNewspaper.changelog(),
+            Self::BigTech => BigTech.changelog(),
+            Self::OldContent => OldContent.changelog(),
+            Self::Pdf => Pdf.changelog(),
+            Self::Video => Video.changelog(),
+            Self::Audio => Audio.changelog(),
+            Self::Paywall => Paywall.changelog(),
+            Self::TellHn => TellHn.changelog(),
+            Self::LaunchHn => LaunchHn.changelog(),
+            Self::Hiring => Hiring.changelog(),
+            Self::Flamewar => Flamewar.changelog(),
+            Self::LowSignal => LowSignal.changelog(),
+            Self::LongRead => LongRead.changelog(),
+            Self::NonEnglish => NonEnglish.changelog(),
+            Self::AiHype => AiHype.changelog(),
+            Self::Crypto => Crypto.changelog(),
+            Self::Politics => Politics.changelog(),
+        }
+    }
+
+    fn dynamic_reason(
+        &self,
+        story: &StoryWithFilters,
+        now: i64,
+    ) -> Option<String> {
+        match self {
+            Self::Flamewar => Flamewar.dynamic_reason(story, now),
+            Self::LowSignal => LowSignal.dynamic_reason(story, now),
+            _ => None,
         }
     }
 }
 
+lazy_static! {
+    // Titles of HN specific submissions start with e.g. "Ask HN:", but people
+    // also write "ask hn :", "AskHN -" etc.
+    static ref HN_PREFIX: Regex =
+        Regex::new(r"(?i)^\s*(ask|launch|show|tell)\s*hn\b").unwrap();
+}
+
+// Does the title start with given kind of HN submission, e.g. "Ask" for
+// "Ask HN"?
+fn hn_prefix(story: &Story, kind: &str) -> Option<String> {
+    let captures = HN_PREFIX.captures(&story.title)?;
+    if captures[1].eq_ignore_ascii_case(kind) {
+        Some(format!("title starts with \"{} HN\"", kind))
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -104,12 +662,371 @@ mod tests {
         let news = "https://www.wsj.com/articles/reddit-claims-52-million-daily-users-revealing-a-key-figure-for-social-media-platforms-11606822200";
         let mut story = Story::random_url();
         story.kind = StoryKind::Url(news.to_string());
-        assert!(LargeNewspaper.should_flag(&story));
+        assert!(LargeNewspaper.reason(&story).is_some());
+
+        let mut story = Story::random_url();
+        story.kind = StoryKind::Url("https://www.bbc.co.uk/news".to_string());
+        assert!(LargeNewspaper.reason(&story).is_some());
+
+        // only the domain matters, not the path
+        story.kind =
+            StoryKind::Url("https://example.com/nytimes.com".to_string());
+        assert!(LargeNewspaper.reason(&story).is_none());
 
         let story = Story::random_url();
-        assert!(!LargeNewspaper.should_flag(&story));
+        assert!(LargeNewspaper.reason(&story).is_none());
 
         let story = Story::random_text();
-        assert!(!LargeNewspaper.should_flag(&story));
+        assert!(LargeNewspaper.reason(&story).is_none());
+    }
+
+    #[test]
+    fn it_should_explain_big_tech() {
+        let mut story = Story::random_url();
+        story.title = "Google and Meta's WhatsApp fined".to_string();
+
+        assert_eq!(
+            Some(
+                "mentions google (\"Google\"), meta (\"Meta\"), \
+                meta (\"WhatsApp\")"
+                    .to_string()
+            ),
+            BigTech.reason(&story)
+        );
+    }
+
+    #[test]
+    fn it_should_match_hn_prefixes() {
+        let titled = |title: &str| {
+            let mut story = Story::random_text();
+            story.title = title.to_string();
+            story
+        };
+
+        for title in &["Ask HN: Why?", "ask hn : why?", "AskHN - why?"] {
+            let story = titled(title);
+            assert!(AskHn.reason(&story).is_some(), "Should flag '{}'", title);
+            assert!(ShowHn.reason(&story).is_none());
+        }
+        for title in &["Show HN: A tool", "  show HN: a tool", "Show Hn:tool"] {
+            assert!(ShowHn.reason(&titled(title)).is_some(), "{}", title);
+        }
+        for title in &["Tell HN: Heads up", "tell hn: heads up"] {
+            assert!(TellHn.reason(&titled(title)).is_some(), "{}", title);
+        }
+        assert_eq!(
+            Some("title starts with \"Launch HN\"".to_string()),
+            LaunchHn.reason(&titled("Launch HN: Acme (YC W21) – Anvils"))
+        );
+
+        for title in &[
+            "Asking for help is a skill",
+            "Why you should ask HN",
+            "Showing HN some love",
+            "Show HNs are great",
+        ] {
+            let story = titled(title);
+            assert!(AskHn.reason(&story).is_none(), "Should pass '{}'", title);
+            assert!(ShowHn.reason(&story).is_none(), "{}", title);
+        }
+    }
+
+    #[test]
+    fn it_should_match_hiring() {
+        let titled = |title: &str| {
+            let mut story = Story::random_text();
+            story.title = title.to_string();
+            story
+        };
+
+        for title in &[
+            "Ask HN: Who is hiring? (March 2021)",
+            "Ask HN: Who wants to be hired? (March 2021)",
+            "Ask HN: Freelancer? Seeking freelancer? (March 2021)",
+            "ask hn: who is hiring? (April 2021)",
+        ] {
+            assert_eq!(
+                Some("monthly hiring thread".to_string()),
+                Hiring.reason(&titled(title)),
+                "{}",
+                title
+            );
+        }
+        assert_eq!(
+            Some("job ad of a YC company".to_string()),
+            Hiring.reason(&titled("Stripe (YC S09) is hiring engineers"))
+        );
+        assert!(Hiring
+            .reason(&titled("Segment (YC S11) Hiring: Backend Engineer"))
+            .is_some());
+
+        for title in &[
+            "Ask HN: Is anyone hiring juniors?",
+            "Why nobody is hiring",
+            "Launch HN: Acme (YC W21) – Anvils",
+        ] {
+            assert!(Hiring.reason(&titled(title)).is_none(), "{}", title);
+        }
+    }
+
+    #[test]
+    fn it_should_match_old_content() {
+        let in_2021 = |title: &str| {
+            let mut story = Story::random_url();
+            story.title = title.to_string();
+            story.submitted_at = Some(1609459200); // 2021-01-01
+            story
+        };
+
+        let flagged = &[
+            "The Mother of All Demos (1968)",
+            "Things you should never do, part I (2000)",
+            "On the cruelty of really teaching computing science [1988]",
+            "Worse is better (1990s)",
+            "Lisp in the 1980s (1980s) [pdf]",
+            "[video] The Mess We're In (2014)",
+            "The Mess We're In (2014) [video]",
+            "Reflections on Trusting Trust (1984) (2004 reprint)",
+            "Postmortem of our outage (2019)",
+        ];
+        for title in flagged {
+            let reason = OldContent.reason(&in_2021(title));
+            assert!(reason.is_some(), "Should flag '{}'", title);
+        }
+        assert_eq!(
+            Some("published in 1999, 22 years before".to_string()),
+            OldContent.reason(&in_2021("Worse is better (1990s)"))
+        );
+        assert_eq!(
+            Some("published in 2004, 17 years before".to_string()),
+            OldContent.reason(&in_2021("Trusting Trust (1984) (2004)"))
+        );
+        assert_eq!(
+            Some("published in 2019, 2 years before".to_string()),
+            OldContent.reason(&in_2021("Postmortem of our outage (2019)"))
+        );
+
+        let not_flagged = &[
+            "Rust 2021 roadmap (2021)",
+            "Year in review (2020)",
+            "A prediction for the year (2030)",
+            "Music of the 1990s",
+            "Fixes issue (#1999)",
+            "Release 2019.1",
+            "Ticket (1234) closed",
+            "The 2020s (2020s)",
+            "Ask HN: Who is hiring? (January 2021)",
+        ];
+        for title in not_flagged {
+            let reason = OldContent.reason(&in_2021(title));
+            assert!(reason.is_none(), "Should pass '{}'", title);
+        }
+
+        let mut ask_hn =
+            in_2021("Ask HN: What old (2000s) software do you use?");
+        ask_hn.kind = StoryKind::Text(String::new());
+        assert!(OldContent.reason(&ask_hn).is_none());
+    }
+
+    #[test]
+    fn it_should_match_long_read() {
+        let mut story = Story::random_url();
+        assert!(LongRead.reason(&story).is_none());
+
+        story.article = Some(Article {
+            word_count: 4600,
+            ..Default::default()
+        });
+        assert_eq!(
+            Some("4600 words, about 20 minutes to read".to_string()),
+            LongRead.reason(&story)
+        );
+
+        story.article = Some(Article {
+            word_count: 800,
+            ..Default::default()
+        });
+        assert!(LongRead.reason(&story).is_none());
+    }
+
+    #[test]
+    fn it_should_match_non_english() {
+        let mut story = Story::random_url();
+        story.title = "Die Geschichte des Internets in Deutschland".to_string();
+        assert_eq!(
+            Some("written in German".to_string()),
+            NonEnglish.reason(&story)
+        );
+
+        // the article text decides if we have it
+        story.article = Some(Article {
+            text: "This is the story of the internet in Germany, from the \
+                first university networks to the broadband of today."
+                .to_string(),
+            ..Default::default()
+        });
+        assert!(NonEnglish.reason(&story).is_none());
+
+        // unknown language is not flagged
+        story.title = "Crafting Interpreters".to_string();
+        story.article = None;
+        assert!(NonEnglish.reason(&story).is_none());
+    }
+
+    #[test]
+    fn it_should_match_discussion() {
+        let now = 1609459200; // 2021-01-01
+        let story = |hours_ago: i64, score: i64, comments: i64| {
+            let mut story = StoryWithFilters::random(vec![]);
+            story.submitted_at = Some(now - hours_ago * 3600);
+            story.score = Some(score);
+            story.comments = Some(comments);
+            story
+        };
+
+        assert_eq!(
+            Some("120 comments on 60 points".to_string()),
+            Flamewar.dynamic_reason(&story(1, 60, 120), now)
+        );
+        // few comments, or many comments on a popular story
+        assert!(Flamewar.dynamic_reason(&story(1, 5, 30), now).is_none());
+        assert!(Flamewar.dynamic_reason(&story(1, 500, 400), now).is_none());
+
+        assert_eq!(
+            Some("12 points after 5 hours".to_string()),
+            LowSignal.dynamic_reason(&story(5, 12, 3), now)
+        );
+        // fresh story is given a chance
+        assert!(LowSignal.dynamic_reason(&story(1, 12, 3), now).is_none());
+        assert!(LowSignal.dynamic_reason(&story(5, 80, 3), now).is_none());
+
+        // stories without the values, e.g. inserted by older versions
+        let unknown = StoryWithFilters::random(vec![]);
+        assert!(Flamewar.dynamic_reason(&unknown, now).is_none());
+        assert!(LowSignal.dynamic_reason(&unknown, now).is_none());
+
+        // never flag at insertion
+        assert!(Flamewar.reason(&Story::random_url()).is_none());
+    }
+
+    #[test]
+    fn it_should_match_media() {
+        let story = |url: &str, title: &str| {
+            let mut story = Story::random_url();
+            story.kind = StoryKind::Url(url.to_string());
+            story.title = title.to_string();
+            story
+        };
+
+        let pdf = story("https://example.com/a", "Paper [PDF]");
+        assert_eq!(Some("title tagged [pdf]".to_string()), Pdf.reason(&pdf));
+        let pdf = story("https://example.com/paper.PDF?dl=1", "Paper");
+        assert_eq!(Some("links to a .pdf file".to_string()), Pdf.reason(&pdf));
+        assert!(Video.reason(&pdf).is_none());
+
+        let video = story("https://www.youtube.com/watch?v=1", "A talk");
+        assert_eq!(
+            Some("links to youtube.com".to_string()),
+            Video.reason(&video)
+        );
+        let video = story("https://example.com/talk.mp4", "A talk");
+        assert!(Video.reason(&video).is_some());
+        let video = story("https://example.com/talk", "A talk [video] (2019)");
+        assert!(Video.reason(&video).is_some());
+        assert!(Pdf.reason(&video).is_none());
+
+        let audio = story("https://soundcloud.com/a/b", "An interview");
+        assert!(Audio.reason(&audio).is_some());
+        let audio = story("https://example.com/ep1", "Episode 1 [podcast]");
+        assert!(Audio.reason(&audio).is_some());
+        let audio = story("https://example.com/ep1.mp3", "Episode 1");
+        assert!(Audio.reason(&audio).is_some());
+
+        // mentions of the media type or the host aren't enough
+        let text = story("https://example.com/pdf", "Why PDF is hard to parse");
+        assert!(Pdf.reason(&text).is_none());
+        let text = story("https://blog.example.com/youtube.com", "YouTube");
+        assert!(Video.reason(&text).is_none());
+        assert!(Audio.reason(&Story::random_text()).is_none());
+    }
+
+    #[test]
+    fn it_should_match_big_tech() {
+        const FLAGGED: &[&str] = &[
+            "Apple unveils M1 Ultra",
+            "Apple's M1 Ultra benchmarks",
+            "Epic v. Apple Inc. ruling",
+            "GOOGLE IS DOWN",
+            "Google's new privacy policy",
+            "Alphabet reports record quarter",
+            "Meta lays off 11,000 employees",
+            "WhatsApp end-to-end encryption explained",
+            "Instagram tests removing likes",
+            "YouTube removes dislike count",
+            "Youtube-dl repository restored",
+            "Amazon is shutting down its Go stores",
+            "Amazon’s Prime Day is coming",
+            "AWS outage in us-east-1",
+            "Microsoft acquires GitHub",
+            "Facebook-owned Oculus requires accounts",
+        ];
+        const NOT_FLAGGED: &[&str] = &[
+            "How to make pineapple pizza",
+            "An apple a day keeps the doctor away",
+            "Metaprogramming in Rust",
+            "The meta problem of consciousness",
+            "Awesome list of alphabets",
+            "Googleplex-sized numbers in combinatorics",
+            "Rewriting the Unicode Awk in Rust",
+            "Show HN: A tiny database",
+            "The Amazon rainforest is now a carbon source",
+            "Why New York is called the Big Apple",
+            "The Amazon River",
+            "Apple II",
+            "Meta: Dataset of dataset descriptions",
+        ];
+
+        for title in FLAGGED {
+            let mut story = Story::random_url();
+            story.title = title.to_string();
+            assert!(
+                BigTech.reason(&story).is_some(),
+                "Should flag '{}'",
+                title
+            );
+        }
+        for title in NOT_FLAGGED {
+            let mut story = Story::random_url();
+            story.title = title.to_string();
+            assert!(
+                BigTech.reason(&story).is_none(),
+                "Should pass '{}'",
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_tell_which_big_tech_keyword_matched() {
+        let mut story = Story::random_url();
+        story.title = "Google and Meta's WhatsApp fined".to_string();
+
+        assert_eq!(
+            vec![
+                KeywordMatch {
+                    group: "google",
+                    keyword: "Google"
+                },
+                KeywordMatch {
+                    group: "meta",
+                    keyword: "Meta"
+                },
+                KeywordMatch {
+                    group: "meta",
+                    keyword: "WhatsApp"
+                },
+            ],
+            BigTech.matches(&story)
+        );
     }
 }
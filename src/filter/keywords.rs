@@ -0,0 +1,210 @@
+//! Title based filters look for keywords. A keyword only matches a whole word,
+//! "Apple" doesn't match "Pineapple", and the case doesn't matter. Company
+//! names which are also common words only match where they refer to the
+//! company.
+//!
+//! Keywords are organized in groups, e.g. all names associated with a company.
+//! A match tells which group and which keyword fired.
+
+use regex::Regex;
+
+/// A word to look for.
+#[derive(Clone, Copy)]
+pub struct Keyword {
+    word: &'static str,
+    context: Context,
+}
+
+#[derive(Clone, Copy)]
+enum Context {
+    /// Matches "google", "Google" and "GOOGLE" anywhere in the text.
+    Any,
+    /// For company names which are also common words, e.g. "Apple". Titles
+    /// capitalize "the Big Apple" too, so the case doesn't tell them apart,
+    /// the surrounding words do. See [`refers_to_company`].
+    Company,
+}
+
+// Articles before a name hint at the common word, e.g. "the Amazon rainforest".
+const ARTICLES: &[&str] = &["a", "an", "the"];
+
+/// Named groups of keywords compiled into regular expressions.
+pub struct KeywordGroups {
+    groups: Vec<(&'static str, Vec<(Keyword, Regex)>)>,
+}
+
+/// Which keyword was found in the text.
+#[derive(Debug, PartialEq)]
+pub struct KeywordMatch {
+    pub group: &'static str,
+    pub keyword: &'static str,
+}
+
+impl Keyword {
+    pub const fn new(word: &'static str) -> Self {
+        Self {
+            word,
+            context: Context::Any,
+        }
+    }
+
+    pub const fn company(word: &'static str) -> Self {
+        Self {
+            word,
+            context: Context::Company,
+        }
+    }
+
+    fn regex(&self) -> Regex {
+        let word = regex::escape(self.word);
+        let pattern = match self.context {
+            Context::Any => format!(r"(?i)\b{}\b", word),
+            // the surrounding words are checked once the name is found
+            Context::Company => format!(r"\b{}\b", word),
+        };
+
+        Regex::new(&pattern).expect("Invalid keyword regex")
+    }
+
+    fn is_match(&self, regex: &Regex, text: &str) -> bool {
+        match self.context {
+            Context::Any => regex.is_match(text),
+            Context::Company => regex
+                .find_iter(text)
+                .any(|m| refers_to_company(text, m.start(), m.end())),
+        }
+    }
+}
+
+impl KeywordGroups {
+    pub fn new(groups: &[(&'static str, &[Keyword])]) -> Self {
+        let groups = groups
+            .iter()
+            .map(|(group, keywords)| {
+                let keywords = keywords
+                    .iter()
+                    .map(|keyword| (*keyword, keyword.regex()))
+                    .collect();
+                (*group, keywords)
+            })
+            .collect();
+
+        Self { groups }
+    }
+
+    /// Returns all keywords found in the text, in the order of the groups.
+    pub fn find(&self, text: &str) -> Vec<KeywordMatch> {
+        self.groups
+            .iter()
+            .flat_map(|(group, keywords)| {
+                keywords
+                    .iter()
+                    .filter(move |(keyword, regex)| {
+                        keyword.is_match(regex, text)
+                    })
+                    .map(move |(keyword, _)| KeywordMatch {
+                        group,
+                        keyword: keyword.word,
+                    })
+            })
+            .collect()
+    }
+}
+
+// Does the name found at given byte range of the text refer to the company?
+// It does in the possessive "Apple's", in "Apple Inc" or "Apple Corp", when it
+// starts the text followed by a lowercase word, typically a verb as in "Apple
+// removes", and when it follows a lowercase word such as in "sues Apple" or
+// "from Apple". An article or a capitalized word before the name, as in "the
+// Big Apple", is the common word.
+fn refers_to_company(text: &str, start: usize, end: usize) -> bool {
+    let after = &text[end..];
+    if after.starts_with("'s") || after.starts_with("’s") {
+        return true;
+    }
+    // e.g. "Meta-analysis"
+    if after.starts_with('-') {
+        return false;
+    }
+
+    let next_word = after
+        .trim_start_matches(',')
+        .split_whitespace()
+        .next()
+        .unwrap_or_default();
+    if matches!(next_word.trim_end_matches('.'), "Inc" | "Corp") {
+        return true;
+    }
+
+    match text[..start].split_whitespace().last() {
+        None => next_word.starts_with(char::is_lowercase),
+        Some(previous) => {
+            previous.chars().all(char::is_lowercase)
+                && !ARTICLES.contains(&previous)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_matches_whole_words() {
+        let groups = KeywordGroups::new(&[
+            ("fruit", &[Keyword::new("kiwi"), Keyword::company("Date")]),
+            ("bird", &[Keyword::new("kiwi")]),
+        ]);
+
+        assert!(!groups.find("KIWI is a fruit").is_empty());
+        assert!(!groups.find("Kiwi's").is_empty());
+        assert!(groups.find("kiwis").is_empty());
+        assert!(groups.find("update the date").is_empty());
+
+        assert_eq!(
+            vec![
+                KeywordMatch {
+                    group: "fruit",
+                    keyword: "kiwi"
+                },
+                KeywordMatch {
+                    group: "bird",
+                    keyword: "kiwi"
+                },
+            ],
+            groups.find("a kiwi")
+        );
+        assert!(groups.find("nothing").is_empty());
+    }
+
+    #[test]
+    fn it_matches_companies_in_context() {
+        let groups =
+            KeywordGroups::new(&[("date", &[Keyword::company("Date")])]);
+
+        for text in &[
+            "Date's new phone",
+            "Date’s new phone",
+            "Why Date Inc. sued",
+            "Date, Inc. sued",
+            "  Date removes the headphone jack",
+            "Why we left Date",
+            "Ask HN: Why is Date so slow?",
+        ] {
+            assert!(!groups.find(text).is_empty(), "Should match '{}'", text);
+        }
+        for text in &[
+            "First Date",
+            "The Date of the release",
+            "Date Night Ideas",
+            "DATE",
+            "Update the date",
+            "Dinner at the Date",
+            "A Blind Date",
+            "Date-based versioning",
+            "Date: A history",
+        ] {
+            assert!(groups.find(text).is_empty(), "Should pass '{}'", text);
+        }
+    }
+}
@@ -3,14 +3,35 @@
 //! Given a story, filter will decide based on the story content whether to flag
 //! it. This information is then written to the database.
 
+pub mod evaluate;
+#[cfg(test)]
+mod golden;
 mod impls;
+mod keywords;
 pub mod page;
+pub mod paywall;
+pub mod topic;
 
 pub use page::Page;
 
-use {lazy_static::lazy_static, regex::Regex};
+use {
+    lazy_static::lazy_static,
+    std::{
+        path::PathBuf,
+        sync::{RwLock, RwLockReadGuard},
+    },
+};
+
+use crate::{
+    conf::{defaults, Conf},
+    prelude::*,
+};
 
-use crate::prelude::*;
+lazy_static! {
+    // Filters are unit structs, they read their thresholds from here. Until
+    // [`init`] is called, e.g. in tests, the defaults apply.
+    static ref SETTINGS: RwLock<Settings> = RwLock::new(Settings::default());
+}
 
 pub trait Filter {
     /// Name of the filter group. For now all filter groups are hard coded.
@@ -18,20 +39,137 @@ pub trait Filter {
     /// code and we can use 'static.
     fn name(&self) -> &'static str;
 
-    /// Does the filter apply to the given story?
-    fn should_flag(&self, story: &Story) -> bool;
+    /// Does the filter apply to the given story? If so, explains why, e.g.
+    /// which keyword or domain matched. The reason is shown to users so that
+    /// they know why a story is missing from their page.
+    fn reason(&self, story: &Story) -> Option<String>;
+
+    /// Filters which depend on values changing over time, such as the score,
+    /// cannot flag a story once when it's inserted. They are evaluated
+    /// whenever pages are rendered instead, `now` being the unix time of the
+    /// render. See [`DYNAMIC_FILTERS`].
+    fn dynamic_reason(
+        &self,
+        _story: &StoryWithFilters,
+        _now: i64,
+    ) -> Option<String> {
+        None
+    }
+
+    /// Changes of the filter logic since the filter was published, oldest
+    /// first. Pages using the filter show a notice about recent changes.
+    fn changelog(&self) -> &'static [Change] {
+        &[]
+    }
+
+    /// The first published version of a filter is 1, each change in the
+    /// changelog bumps it.
+    fn version(&self) -> u32 {
+        version_after(self.changelog().len())
+    }
+}
+
+/// A change of the filter logic which users should know about.
+#[derive(Debug)]
+pub struct Change {
+    /// Day when the change was made as `YYYY-MM-DD`. The notice period starts
+    /// when we first run with the change, see [`db::insert_filter_versions`].
+    /// The day is only used for versions which were deployed before we
+    /// recorded that.
+    ///
+    /// [`db::insert_filter_versions`]: crate::db::insert_filter_versions
+    pub day: &'static str,
+    /// Short description of the change for the users.
+    pub description: &'static str,
 }
 
 // IMPORTANT: This needs to be sorted based on name.
-const FILTERS: &[FilterKind] = &[
+pub const FILTERS: &[FilterKind] = &[
+    FilterKind::AiHype,
     FilterKind::BigTech,
     FilterKind::AskHn,
+    FilterKind::Audio,
     FilterKind::LargeNewspaper,
+    FilterKind::Crypto,
+    FilterKind::Hiring,
+    FilterKind::LaunchHn,
+    FilterKind::LongRead,
+    FilterKind::NonEnglish,
+    FilterKind::OldContent,
+    FilterKind::Paywall,
+    FilterKind::Pdf,
+    FilterKind::Politics,
     FilterKind::ShowHn,
+    FilterKind::TellHn,
+    FilterKind::Video,
 ];
 
-/// Given stories, returns a list of filters which flagged each story.
-/// The output vector is of the same size as the input.
+/// Filters which only flag stories when pages are rendered, see
+/// [`Filter::dynamic_reason`]. Their flags aren't stored in the database.
+///
+/// IMPORTANT: This needs to be sorted based on name.
+pub const DYNAMIC_FILTERS: &[FilterKind] =
+    &[FilterKind::Flamewar, FilterKind::LowSignal];
+
+/// Thresholds of the filters which can be configured, see [`Conf`] for their
+/// description.
+#[derive(Debug)]
+pub struct Settings {
+    pub old_content_min_age_years: i64,
+    pub paywall_domains: Vec<String>,
+    pub long_read_min_words: usize,
+    pub flamewar_min_comments: i64,
+    pub flamewar_comments_per_point: f64,
+    pub low_signal_min_score: i64,
+    pub low_signal_after_hours: i64,
+    pub topic_models_dir: Option<PathBuf>,
+}
+
+/// Configures the filters. Called once at startup before any filter runs.
+pub fn init(conf: &Conf) {
+    *SETTINGS.write().unwrap() = Settings {
+        old_content_min_age_years: conf.old_content_min_age_years,
+        paywall_domains: conf.paywall_domains.clone(),
+        long_read_min_words: conf.long_read_min_words,
+        flamewar_min_comments: conf.flamewar_min_comments,
+        flamewar_comments_per_point: conf.flamewar_comments_per_point,
+        low_signal_min_score: conf.low_signal_min_score,
+        low_signal_after_hours: conf.low_signal_after_hours,
+        topic_models_dir: conf.topic_models_dir.clone(),
+    };
+}
+
+fn settings() -> RwLockReadGuard<'static, Settings> {
+    SETTINGS.read().unwrap()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            old_content_min_age_years: defaults::OLD_CONTENT_MIN_AGE_YEARS,
+            paywall_domains: vec![],
+            long_read_min_words: defaults::LONG_READ_MIN_WORDS,
+            flamewar_min_comments: defaults::FLAMEWAR_MIN_COMMENTS,
+            flamewar_comments_per_point: defaults::FLAMEWAR_COMMENTS_PER_POINT,
+            low_signal_min_score: defaults::LOW_SIGNAL_MIN_SCORE,
+            low_signal_after_hours: defaults::LOW_SIGNAL_AFTER_HOURS,
+            topic_models_dir: None,
+        }
+    }
+}
+
+/// Version of a filter after given number of changes in its changelog.
+pub fn version_after(changes: usize) -> u32 {
+    changes as u32 + 1
+}
+
+/// Finds the filter with given name, see [`Filter::name`].
+pub fn by_name(name: &str) -> Option<FilterKind> {
+    FILTERS.iter().copied().find(|filter| filter.name() == name)
+}
+
+/// Given stories, returns a list of filters which flagged each story along with
+/// the reason. The output vector is of the same size as the input.
 pub fn for_stories(stories: &[Story]) -> Vec<StoryFilters> {
     stories
         .iter()
@@ -39,7 +177,7 @@ pub fn for_stories(stories: &[Story]) -> Vec<StoryFilters> {
             let story_filters = FILTERS
                 .iter()
                 .copied()
-                .filter(|f| f.should_flag(story))
+                .filter_map(|f| f.reason(story).map(|reason| (f, reason)))
                 .collect();
 
             (story.id, story_filters)
@@ -47,6 +185,16 @@ pub fn for_stories(stories: &[Story]) -> Vec<StoryFilters> {
         .collect()
 }
 
+/// Flags the story with the dynamic filters which apply at given unix time.
+pub fn apply_dynamic(story: &mut StoryWithFilters, now: i64) {
+    for filter in DYNAMIC_FILTERS.iter().copied() {
+        if let Some(reason) = filter.dynamic_reason(story, now) {
+            story.filters.insert(filter);
+            story.filter_reasons.insert(filter, reason);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,6 +208,25 @@ mod tests {
         // debug_assert!(FILTERS.is_sorted_by(|a, b| a.name().cmp(b.name())));
     }
 
+    #[test]
+    fn it_has_valid_changelogs() {
+        for filter in FILTERS {
+            let days: Vec<_> = filter
+                .changelog()
+                .iter()
+                .map(|change| crate::date::parse_day(change.day).unwrap())
+                .collect();
+
+            let mut sorted_days = days.clone();
+            sorted_days.sort_unstable();
+            assert_eq!(sorted_days, days, "Unsorted changelog of {}", filter);
+        }
+
+        assert_eq!(1, FilterKind::TellHn.version());
+        assert_eq!(2, FilterKind::AskHn.version());
+        assert_eq!(2, FilterKind::BigTech.version());
+    }
+
     #[test]
     fn it_picks_filters_for_stories() {
         let bbc_google_story = {
@@ -84,11 +251,29 @@ mod tests {
         assert_eq!(
             (
                 bbc_google_story_id,
-                vec![FilterKind::BigTech, FilterKind::LargeNewspaper]
+                vec![
+                    (
+                        FilterKind::BigTech,
+                        "mentions google (\"Google\")".to_string()
+                    ),
+                    (
+                        FilterKind::LargeNewspaper,
+                        "links to bbc.com".to_string()
+                    )
+                ]
             ),
             filters[0]
         );
-        assert_eq!((ask_hn_story_id, vec![FilterKind::AskHn]), filters[1]);
+        assert_eq!(
+            (
+                ask_hn_story_id,
+                vec![(
+                    FilterKind::AskHn,
+                    "title starts with \"Ask HN\"".to_string()
+                )]
+            ),
+            filters[1]
+        );
         assert!(filters[2].1.is_empty());
     }
 }
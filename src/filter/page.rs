@@ -4,11 +4,11 @@
 
 use {
     rusqlite::Connection,
-    std::{io, rc::Rc},
+    std::{collections::HashSet, io, rc::Rc},
     tokio::fs,
 };
 
-use crate::{conf, db, html::Template, prelude::*};
+use crate::{conf, date, db, filter, html::Template, prelude::*};
 
 #[derive(Debug)]
 pub struct Page {
@@ -41,6 +41,103 @@ impl Page {
         Self::new(&[Modifier::Without(FilterKind::ShowHn)])
     }
 
+    pub fn tell_hn() -> Self {
+        Self::new(&[Modifier::With(FilterKind::TellHn)])
+    }
+
+    pub fn no_tell_hn() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::TellHn)])
+    }
+
+    pub fn launch_hn() -> Self {
+        Self::new(&[Modifier::With(FilterKind::LaunchHn)])
+    }
+
+    pub fn no_launch_hn() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::LaunchHn)])
+    }
+
+    pub fn hiring() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Hiring)])
+    }
+
+    pub fn no_hiring() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Hiring)])
+    }
+
+    pub fn long_read() -> Self {
+        Self::new(&[Modifier::With(FilterKind::LongRead)])
+    }
+
+    pub fn no_long_read() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::LongRead)])
+    }
+
+    pub fn non_english() -> Self {
+        Self::new(&[Modifier::With(FilterKind::NonEnglish)])
+    }
+
+    /// Only stories in English or in a language we cannot tell.
+    pub fn no_non_english() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::NonEnglish)])
+    }
+
+    pub fn ai_hype() -> Self {
+        Self::new(&[Modifier::With(FilterKind::AiHype)])
+    }
+
+    pub fn no_ai_hype() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::AiHype)])
+    }
+
+    pub fn crypto() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Crypto)])
+    }
+
+    pub fn no_crypto() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Crypto)])
+    }
+
+    pub fn politics() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Politics)])
+    }
+
+    pub fn no_politics() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Politics)])
+    }
+
+    pub fn flamewar() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Flamewar)])
+    }
+
+    pub fn no_flamewar() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Flamewar)])
+    }
+
+    pub fn low_signal() -> Self {
+        Self::new(&[Modifier::With(FilterKind::LowSignal)])
+    }
+
+    pub fn no_low_signal() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::LowSignal)])
+    }
+
+    /// Only stories with a calm discussion which are worth the points.
+    pub fn no_flamewar_no_low_signal() -> Self {
+        Self::new(&[
+            Modifier::Without(FilterKind::Flamewar),
+            Modifier::Without(FilterKind::LowSignal),
+        ])
+    }
+
+    /// Without YC company launches and job ads.
+    pub fn no_hiring_no_launch_hn() -> Self {
+        Self::new(&[
+            Modifier::Without(FilterKind::Hiring),
+            Modifier::Without(FilterKind::LaunchHn),
+        ])
+    }
+
     pub fn bignews() -> Self {
         Self::new(&[Modifier::With(FilterKind::LargeNewspaper)])
     }
@@ -57,6 +154,55 @@ impl Page {
         Self::new(&[Modifier::Without(FilterKind::BigTech)])
     }
 
+    pub fn old() -> Self {
+        Self::new(&[Modifier::With(FilterKind::OldContent)])
+    }
+
+    pub fn no_old() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::OldContent)])
+    }
+
+    pub fn pdf() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Pdf)])
+    }
+
+    pub fn no_pdf() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Pdf)])
+    }
+
+    pub fn video() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Video)])
+    }
+
+    pub fn no_video() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Video)])
+    }
+
+    pub fn audio() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Audio)])
+    }
+
+    pub fn no_audio() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Audio)])
+    }
+
+    pub fn paywall() -> Self {
+        Self::new(&[Modifier::With(FilterKind::Paywall)])
+    }
+
+    pub fn no_paywall() -> Self {
+        Self::new(&[Modifier::Without(FilterKind::Paywall)])
+    }
+
+    /// Only stories which can be read, useful e.g. at work.
+    pub fn no_audio_no_pdf_no_video() -> Self {
+        Self::new(&[
+            Modifier::Without(FilterKind::Audio),
+            Modifier::Without(FilterKind::Pdf),
+            Modifier::Without(FilterKind::Video),
+        ])
+    }
+
     pub fn no_bignews_no_bigtech() -> Self {
         Self::new(&[
             Modifier::Without(FilterKind::LargeNewspaper),
@@ -71,19 +217,53 @@ impl Page {
         ])
     }
 
+    /// Latest stories linking to given domain, named "@{domain}".
+    pub fn site(domain: &str) -> Self {
+        let mut page = Self::new(&[]);
+        page.name = format!("@{}", domain);
+        page
+    }
+
     /// Returns the name of the page. This should be used for the S3 object.
     /// Users will access the page at `https://${domain}/${name}`.
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Filters which decide what stories are on the page.
+    pub fn filters(&self) -> impl Iterator<Item = FilterKind> + '_ {
+        self.modifiers.iter().map(|modifier| match modifier {
+            Modifier::With(filter) | Modifier::Without(filter) => *filter,
+        })
+    }
+
+    /// Only the page with all stories tells why filters flagged each story,
+    /// on other pages it'd be noise.
+    pub fn shows_filter_reasons(&self) -> bool {
+        self.modifiers.is_empty() && self.name == "+all"
+    }
+
     pub fn stories(&self) -> &[Rc<StoryWithFilters>] {
         &self.stories
     }
 
+    /// Ids of the stories on the page in the order they are rendered.
+    pub fn story_ids(&self) -> Vec<StoryId> {
+        self.stories.iter().map(|story| story.id).collect()
+    }
+
     /// If the story was flagged by filters this page is happy with, push it to
-    /// the list of stories we render for this page.
+    /// the list of stories we render for this page. A repost of a link which
+    /// is already on the page is collapsed into the story pushed first.
     pub fn push(&mut self, story: Rc<StoryWithFilters>) {
+        let is_repost = self
+            .stories
+            .iter()
+            .any(|pushed| pushed.canonical_url == story.canonical_url);
+        if is_repost {
+            return;
+        }
+
         // all "-" modifiers are conjunctive
         let removed_from_page = || {
             self.modifiers
@@ -152,37 +332,14 @@ impl Page {
     ) -> Result<()> {
         let html = html_engine.render(self, theme)?;
 
-        if conf.store_html_locally {
-            let file_path = format!("pages/{}/{}.html", theme, self.name());
-
-            log::trace!("Storing page {} ({})...", file_path, theme);
-            fs::write(file_path, html.as_bytes()).await?;
-            Ok(())
-        } else {
-            let object_path = theme.object_path(self.name());
-
-            log::trace!("Uploading page {} ({})...", object_path, theme);
-            let (_, code) = conf
-                .bucket
-                .put_object_with_content_type(
-                    object_path.as_ref(),
-                    html.as_bytes(),
-                    "text/html",
-                )
-                .await?;
-
-            if code != 200 {
-                log::error!(
-                    "Cannot upload page {} (code {})",
-                    self.name(),
-                    code
-                );
-                // hack to return error
-                Err(Box::new(io::Error::from_raw_os_error(1)))
-            } else {
-                Ok(())
-            }
-        }
+        put_object(
+            conf,
+            &format!("pages/{}/{}.html", theme, self.name()),
+            &theme.object_path(self.name()),
+            html.as_bytes(),
+            "text/html",
+        )
+        .await
     }
 
     fn new(modifiers: &'static [Modifier]) -> Self {
@@ -210,6 +367,36 @@ impl Page {
     }
 }
 
+/// Stores the content in the "pages" directory under the local path if we
+/// don't upload html, otherwise uploads it to S3 under the object path.
+pub async fn put_object(
+    conf: &conf::Conf,
+    local_path: &str,
+    object_path: &str,
+    content: &[u8],
+    content_type: &str,
+) -> Result<()> {
+    if conf.store_html_locally {
+        log::trace!("Storing {}...", local_path);
+        fs::write(local_path, content).await?;
+        Ok(())
+    } else {
+        log::trace!("Uploading {}...", object_path);
+        let (_, code) = conf
+            .bucket
+            .put_object_with_content_type(object_path, content, content_type)
+            .await?;
+
+        if code != 200 {
+            log::error!("Cannot upload {} (code {})", object_path, code);
+            // hack to return error
+            Err(Box::new(io::Error::from_raw_os_error(1)))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Creates list of pages for sucklesshn and populates them with stories from
 /// the database.
 pub fn populate(
@@ -218,8 +405,9 @@ pub fn populate(
     page_limit: usize,
 ) -> Vec<Page> {
     let mut pages = list();
+    let now = date::now();
     for top_story_id in top_stories {
-        if let Ok(Some(story)) = db::select_story(conn, top_story_id) {
+        if let Some(story) = select_story(conn, top_story_id, now) {
             let story = Rc::new(story);
             for page in &mut pages {
                 if page.len() < page_limit {
@@ -238,21 +426,117 @@ pub fn populate(
     pages
 }
 
-// List of all suckless  pages.
+/// Creates a page for each of given domains which we have at least
+/// `min_stories` stories from. Unlike the other pages, these list the latest
+/// stories we stored rather than the current top stories, hence only pages of
+/// the domains of newly stored stories change.
+pub fn populate_sites(
+    conn: &Connection,
+    domains: &HashSet<String>,
+    min_stories: usize,
+    page_limit: usize,
+) -> Vec<Page> {
+    let frequent = match db::select_frequent_domains(conn, min_stories) {
+        Ok(frequent) => frequent,
+        Err(e) => {
+            log::error!("Cannot select frequent domains: {}", e);
+            return vec![];
+        }
+    };
+
+    let now = date::now();
+    frequent
+        .iter()
+        .filter(|domain| domains.contains(*domain))
+        .map(|domain| {
+            let mut page = Page::site(domain);
+            let story_ids =
+                db::select_stories_from_domain(conn, domain, page_limit)
+                    .unwrap_or_default();
+            for story_id in story_ids {
+                if let Some(story) = select_story(conn, story_id, now) {
+                    page.push(Rc::new(story));
+                }
+            }
+            page
+        })
+        .collect()
+}
+
+// Selects the story from the database and flags it with the dynamic filters,
+// whose flags aren't stored.
+fn select_story(
+    conn: &Connection,
+    story_id: StoryId,
+    now: i64,
+) -> Option<StoryWithFilters> {
+    let mut story = db::select_story(conn, story_id).ok()??;
+    filter::apply_dynamic(&mut story, now);
+
+    Some(story)
+}
+
+// List of all suckless  pages. Pages of topic filters without a trained model
+// would be the same as "+all" or empty, we skip them.
 fn list() -> Vec<Page> {
-    vec![
+    let pages = vec![
+        Page::ai_hype(),
         Page::all(),
         Page::ask_hn(),
         Page::ask_show_hn(),
+        Page::audio(),
         Page::bignews(),
         Page::bigtech(),
+        Page::crypto(),
+        Page::flamewar(),
+        Page::hiring(),
+        Page::launch_hn(),
+        Page::long_read(),
+        Page::low_signal(),
+        Page::no_ai_hype(),
         Page::no_ask_hn(),
+        Page::no_audio(),
+        Page::no_audio_no_pdf_no_video(),
         Page::no_bignews_no_bigtech(),
         Page::no_bignews(),
         Page::no_bigtech(),
+        Page::no_crypto(),
+        Page::no_flamewar_no_low_signal(),
+        Page::no_flamewar(),
+        Page::no_hiring(),
+        Page::no_hiring_no_launch_hn(),
+        Page::no_launch_hn(),
+        Page::no_long_read(),
+        Page::no_low_signal(),
+        Page::no_non_english(),
+        Page::no_old(),
+        Page::no_paywall(),
+        Page::no_pdf(),
+        Page::no_politics(),
         Page::no_show_hn(),
+        Page::no_tell_hn(),
+        Page::no_video(),
+        Page::non_english(),
+        Page::old(),
+        Page::paywall(),
+        Page::pdf(),
+        Page::politics(),
         Page::show_hn(),
-    ]
+        Page::tell_hn(),
+        Page::video(),
+    ];
+
+    pages
+        .into_iter()
+        .filter(|page| {
+            page.modifiers.iter().all(|modifier| match modifier {
+                Modifier::With(filter) | Modifier::Without(filter) => {
+                    !filter::topic::TOPICS.contains(filter)
+                        || filter::topic::is_trained(*filter)
+                }
+            })
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -306,6 +590,31 @@ mod tests {
         ask_show_hn_page.push(Rc::clone(&ask_hn_story));
         assert_eq!(2, ask_show_hn_page.stories.len());
         assert_eq!("+askhn+showhn", ask_show_hn_page.name());
+
+        let old_story = Rc::new(StoryWithFilters::random(vec![OldContent]));
+        let mut no_old_page = Page::no_old();
+        no_old_page.push(Rc::clone(&old_story));
+        assert!(no_old_page.stories.is_empty());
+        no_old_page.push(Rc::clone(&empty_story));
+        assert_eq!(1, no_old_page.stories.len());
+        assert_eq!("-old", no_old_page.name());
+
+        assert_eq!("-audio-pdf-video", Page::no_audio_no_pdf_no_video().name());
+        assert_eq!("-hiring-launchhn", Page::no_hiring_no_launch_hn().name());
+    }
+
+    #[test]
+    fn it_collapses_reposts() {
+        let story = StoryWithFilters::random(vec![]);
+        let mut repost = StoryWithFilters::random(vec![]);
+        repost.canonical_url = story.canonical_url.clone();
+
+        let mut page = Page::all();
+        page.push(Rc::new(story));
+        page.push(Rc::new(repost));
+        page.push(Rc::new(StoryWithFilters::random(vec![])));
+
+        assert_eq!(2, page.len());
     }
 
     #[test]
@@ -343,4 +652,79 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn it_populates_pages_with_dynamic_filters() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+
+        let story = |hours_ago: i64, score: i64, comments: i64| {
+            let mut story = Story::random_url();
+            story.submitted_at = Some(date::now() - hours_ago * 3600);
+            story.score = Some(score);
+            story.comments = Some(comments);
+            (story, vec![])
+        };
+        let stories = &[
+            story(1, 50, 200),
+            story(10, 5, 1),
+            story(10, 300, 100),
+            story(1, 3, 0),
+        ];
+        let ids = stories.iter().map(|(story, _)| story.id).collect();
+        db::tests::insert_test_data(&conn, stories)?;
+
+        let pages: HashMap<_, _> = populate(&conn, ids, 10)
+            .into_iter()
+            .map(|page| (page.name().to_string(), page))
+            .collect();
+
+        let flamewar = pages.get("+flamewar").unwrap();
+        assert_eq!(vec![stories[0].0.id], flamewar.story_ids());
+        let low_signal = pages.get("+lowsignal").unwrap();
+        assert_eq!(vec![stories[1].0.id], low_signal.story_ids());
+        assert_eq!(2, pages.get("-flamewar-lowsignal").unwrap().len());
+
+        let all = pages.get("+all").unwrap();
+        assert_eq!(
+            Some(&"200 comments on 50 points".to_string()),
+            all.stories()[0].filter_reasons.get(&Flamewar)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_populates_site_pages() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+
+        let url_story = |url: &str| {
+            let mut story = Story::random_url();
+            story.kind = StoryKind::Url(url.to_string());
+            (story, vec![])
+        };
+        let stories = &[
+            url_story("https://example.com/a"),
+            url_story("https://blog.example.com/b"),
+            url_story("https://example.com/c"),
+            url_story("https://other.org/d"),
+        ];
+        db::tests::insert_test_data(&conn, stories)?;
+
+        let domains: HashSet<_> = ["example.com", "other.org"]
+            .iter()
+            .map(|domain| domain.to_string())
+            .collect();
+        let pages = populate_sites(&conn, &domains, 2, 2);
+
+        assert_eq!(1, pages.len());
+        assert_eq!("@example.com", pages[0].name());
+        assert_eq!(2, pages[0].len());
+
+        // no new story from the domain, the page didn't change
+        let domains: HashSet<_> =
+            Some("other.org".to_string()).into_iter().collect();
+        assert!(populate_sites(&conn, &domains, 2, 2).is_empty());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,129 @@
+//! Sites which aren't on the paywall list can still hide their articles behind
+//! a paywall. If enabled, we look for markers which publishers and paywall
+//! vendors leave in the html of the linked pages, as fetched by
+//! [`crate::article`].
+
+use super::*;
+
+// Lowercase needles searched for in the html with all whitespace removed,
+// along with a label for the reason.
+const MARKERS: &[(&str, &str)] = &[
+    (r#""isaccessibleforfree":false"#, "isAccessibleForFree"),
+    (r#""isaccessibleforfree":"false""#, "isAccessibleForFree"),
+    ("tinypass.com", "Piano paywall script"),
+    ("cdn.piano.io", "Piano paywall script"),
+    (r#"class="paywall""#, "paywall element"),
+    (r#"id="paywall""#, "paywall element"),
+    ("data-paywall", "paywall element"),
+];
+
+/// Flags stories which aren't flagged by the [`FilterKind::Paywall`] yet and
+/// whose linked page has a paywall marker. The filters must be in the same
+/// order as the stories, as returned by [`for_stories`].
+pub fn detect(stories: &[Story], filters: &mut [StoryFilters]) {
+    for (story, (_, flags)) in stories.iter().zip(filters.iter_mut()) {
+        let flagged = flags.iter().any(|(f, _)| *f == FilterKind::Paywall);
+        let marker = story
+            .article
+            .as_ref()
+            .and_then(|article| article.paywall_marker);
+
+        if let (false, Some(marker)) = (flagged, marker) {
+            let reason = format!("page has a paywall marker ({})", marker);
+            flags.push((FilterKind::Paywall, reason));
+        }
+    }
+}
+
+/// Label of the first paywall marker found in the html.
+pub fn find_marker(html: &str) -> Option<&'static str> {
+    let html: String =
+        html.split_whitespace().collect::<String>().to_lowercase();
+
+    MARKERS
+        .iter()
+        .find(|(needle, _)| html.contains(needle))
+        .map(|(_, label)| *label)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            article,
+            stand_in::{Response, StandIn},
+        },
+        reqwest::Client,
+        std::time::Duration,
+    };
+
+    #[tokio::test]
+    async fn it_detects_paywall_markers() {
+        let stand_in = StandIn::start(|req| match req.path.as_str() {
+            "/schema" => Response::ok(
+                r#"<script type="application/ld+json">
+                { "isAccessibleForFree": "False" }</script>"#,
+            ),
+            "/piano" => Response::ok(
+                r#"<script src="https://cdn.piano.io/api/tinypass.min.js">"#,
+            ),
+            "/missing" => Response::status(404, ""),
+            _ => Response::ok("<article>Free to read</article>"),
+        });
+
+        let mut stories: Vec<_> = ["/schema", "/piano", "/free", "/missing"]
+            .iter()
+            .map(|path| {
+                let mut story = Story::random_url();
+                story.kind =
+                    StoryKind::Url(format!("{}{}", stand_in.url, path));
+                story
+            })
+            .collect();
+        article::fetch_for_stories(
+            &Client::new(),
+            &mut stories,
+            1024,
+            Duration::from_secs(5),
+        )
+        .await;
+        stories.push(Story::random_text());
+        let mut wsj_story = Story::random_url();
+        wsj_story.kind =
+            StoryKind::Url("https://www.wsj.com/articles/a".to_string());
+        wsj_story.article = Some(article::extract(
+            r#"<div class="paywall">Subscribe to read</div>"#,
+        ));
+        stories.push(wsj_story);
+
+        let mut filters = for_stories(&stories);
+        detect(&stories, &mut filters);
+
+        let paywall_reasons: Vec<_> = filters
+            .iter()
+            .map(|(_, flags)| {
+                flags
+                    .iter()
+                    .filter(|(f, _)| *f == FilterKind::Paywall)
+                    .map(|(_, reason)| reason.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                vec!["page has a paywall marker (isAccessibleForFree)"],
+                vec!["page has a paywall marker (Piano paywall script)"],
+                vec![],
+                vec![],
+                vec![],
+                // stories on the paywall list aren't flagged twice
+                vec!["links to wsj.com"],
+            ],
+            paywall_reasons
+        );
+
+        // the pages are fetched once, by the article stage
+        assert_eq!(4, stand_in.requests().len());
+    }
+}
@@ -0,0 +1,544 @@
+//! Keyword lists for broad topics such as crypto or politics are unbearable to
+//! maintain by hand. Topic filters instead flag stories with a naive Bayes
+//! classifier trained offline from stories we labelled as on or off the topic.
+//!
+//! The classifier stays inspectable: each token of a story, i.e. a lowercase
+//! word of the title or the domain of the link, contributes its log-odds of
+//! the story being on topic. The contributions are summed with the prior
+//! log-odds and a positive sum flags the story. The model is a plain json
+//! file and the `explain-topic` command prints the contribution of each token.
+//!
+//! Topic filters are opt-in. Without a trained model in `TOPIC_MODELS_DIR`
+//! they never flag anything. Each training changes what the filter flags, it's
+//! recorded in the changelog of the filter and bumps its version.
+
+use {
+    lazy_static::lazy_static,
+    rusqlite::Connection,
+    serde::{Deserialize, Serialize},
+    std::{
+        cmp::Ordering,
+        collections::{BTreeMap, BTreeSet, HashMap},
+        fmt, fs, io,
+        path::{Path, PathBuf},
+    },
+};
+
+use {
+    super::*,
+    crate::{date, db},
+};
+
+/// Filters backed by a trained classifier.
+pub const TOPICS: &[FilterKind] =
+    &[FilterKind::AiHype, FilterKind::Crypto, FilterKind::Politics];
+
+// Tokens seen in fewer labelled stories tell us more about the labelled sample
+// than about the topic.
+const MIN_TOKEN_STORIES: usize = 2;
+
+// How many tokens of each kind we print about a trained model.
+const TOP_TOKENS: usize = 15;
+
+// How many tokens we show users as the reason of a flag.
+const REASON_TOKENS: usize = 3;
+
+lazy_static! {
+    // Loaded once, a retrained model is used after restart.
+    static ref MODELS: HashMap<FilterKind, Model> = TOPICS
+        .iter()
+        .filter_map(|topic| match Model::load(*topic) {
+            Ok(model) => model.map(|model| (*topic, model)),
+            Err(e) => {
+                log::warn!("Cannot load model of topic {}: {}", topic, e);
+                None
+            }
+        })
+        .collect();
+
+    // Borrows the training days of the loaded models.
+    static ref CHANGELOGS: HashMap<FilterKind, Vec<Change>> = MODELS
+        .iter()
+        .map(|(topic, model)| (*topic, model.changelog()))
+        .collect();
+}
+
+/// Log-odds of each token, see the module docs.
+#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct Model {
+    /// How many labelled stories were on topic.
+    pub positives: usize,
+    /// How many labelled stories were off topic.
+    pub negatives: usize,
+    /// Log-odds of a story being on topic before we look at its tokens.
+    pub prior: f64,
+    /// Log-odds contributed by each token present in a story.
+    pub tokens: BTreeMap<String, f64>,
+    /// Days on which the topic was trained as `YYYY-MM-DD`, oldest first.
+    /// Models trained before we recorded this have none.
+    #[serde(default)]
+    pub trained_on: Vec<String>,
+}
+
+/// How the classifier of a topic decides about a stored story.
+pub struct Explanation {
+    pub topic: FilterKind,
+    pub story_id: StoryId,
+    pub title: String,
+    pub prior: f64,
+    pub score: f64,
+    /// Tokens of the story known to the model with their log-odds, the most
+    /// telling first.
+    pub contributions: Vec<(String, f64)>,
+    /// Why the story is flagged, if it is.
+    pub reason: Option<String>,
+}
+
+/// Why the topic filter flags the story, if it has a trained model and does.
+pub fn reason(topic: FilterKind, story: &Story) -> Option<String> {
+    MODELS.get(&topic)?.reason(story)
+}
+
+/// Each training of the topic model is a change of the filter, see
+/// [`Filter::changelog`].
+pub fn changelog(topic: FilterKind) -> &'static [Change] {
+    CHANGELOGS.get(&topic).map(Vec::as_slice).unwrap_or(&[])
+}
+
+/// Whether the topic filter has a trained model and can flag stories.
+pub fn is_trained(topic: FilterKind) -> bool {
+    MODELS.contains_key(&topic)
+}
+
+/// Finds the topic filter with given name.
+pub fn by_name(name: &str) -> Option<FilterKind> {
+    TOPICS.iter().copied().find(|topic| topic.name() == name)
+}
+
+/// Lowercase words of the title with at least two characters and the domain
+/// of the link. Each token counts once per story.
+pub fn tokens(story: &Story) -> BTreeSet<String> {
+    let words = story
+        .title
+        .to_lowercase()
+        // keeps tokens such as "c++" or "c#" whole
+        .split(|c: char| !(c.is_alphanumeric() || c == '+' || c == '#'))
+        .filter(|word| {
+            word.chars().count() > 1 && word.chars().any(char::is_alphanumeric)
+        })
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    // domains contain a dot, they never clash with words
+    words.into_iter().chain(story.domain()).collect()
+}
+
+/// Loads labelled stories of the topic from the database and trains its model.
+/// The training is appended to the days on which the saved model was trained.
+pub fn train_from_db(conn: &Connection, topic: FilterKind) -> Result<Model> {
+    let mut examples = vec![];
+    for (story_id, on_topic) in db::select_topic_labels(conn, topic)? {
+        match db::select_story(conn, story_id)? {
+            Some(stored) => {
+                examples.push((evaluate::as_story(&stored), on_topic))
+            }
+            None => log::warn!("Labelled story {} is not stored", story_id),
+        }
+    }
+
+    let mut model = Model::train(&examples)?;
+    model.trained_on = Model::load(topic)?
+        .map(|previous| previous.trained_on)
+        .unwrap_or_default();
+    model.trained_on.push(date::format_day(date::now()));
+
+    Ok(model)
+}
+
+/// Explains how the saved model of the topic decides about a stored story.
+pub fn explain(
+    conn: &Connection,
+    topic: FilterKind,
+    story_id: StoryId,
+) -> Result<Explanation> {
+    let model = Model::load(topic)?.ok_or_else(|| {
+        format!("Topic {} has no model, run train-topic first", topic)
+    })?;
+    let stored = db::select_story(conn, story_id)?
+        .ok_or_else(|| format!("Story {} is not stored", story_id))?;
+    let story = evaluate::as_story(&stored);
+
+    Ok(Explanation {
+        topic,
+        story_id,
+        title: stored.title,
+        prior: model.prior,
+        score: model.score(&story),
+        contributions: model.contributions(&story),
+        reason: model.reason(&story),
+    })
+}
+
+impl Model {
+    /// Each example is a story and whether it's on topic. We need at least
+    /// one story of each kind.
+    pub fn train(examples: &[(Story, bool)]) -> Result<Self> {
+        let positives =
+            examples.iter().filter(|(_, on_topic)| *on_topic).count();
+        let negatives = examples.len() - positives;
+        if positives == 0 || negatives == 0 {
+            return Err(
+                "Label at least one story on and one off the topic".into()
+            );
+        }
+
+        // in how many stories on and off topic the token is
+        let mut counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for (story, on_topic) in examples {
+            for token in tokens(story) {
+                let (on, off) = counts.entry(token).or_default();
+                if *on_topic {
+                    *on += 1;
+                } else {
+                    *off += 1;
+                }
+            }
+        }
+
+        let tokens = counts
+            .into_iter()
+            .filter(|(_, (on, off))| on + off >= MIN_TOKEN_STORIES)
+            .map(|(token, (on, off))| {
+                // Laplace smoothing, a token seen only on topic must not make
+                // the odds infinite
+                let p_on = (on as f64 + 1.0) / (positives as f64 + 2.0);
+                let p_off = (off as f64 + 1.0) / (negatives as f64 + 2.0);
+                (token, (p_on / p_off).ln())
+            })
+            .collect();
+
+        Ok(Self {
+            positives,
+            negatives,
+            prior: (positives as f64 / negatives as f64).ln(),
+            tokens,
+            trained_on: vec![],
+        })
+    }
+
+    // Each training is a change of the filter, the first one too as without a
+    // model the filter flags nothing.
+    fn changelog(&'static self) -> Vec<Change> {
+        self.trained_on
+            .iter()
+            .map(|day| Change {
+                day,
+                description: "The classifier was trained on the stories \
+                labelled so far.",
+            })
+            .collect()
+    }
+
+    /// Tokens of the story known to the model with their log-odds, sorted by
+    /// how much they move the score.
+    pub fn contributions(&self, story: &Story) -> Vec<(String, f64)> {
+        let mut contributions: Vec<_> = tokens(story)
+            .into_iter()
+            .filter_map(|token| {
+                let log_odds = *self.tokens.get(&token)?;
+                Some((token, log_odds))
+            })
+            .collect();
+        contributions.sort_by(|(_, a), (_, b)| {
+            b.abs().partial_cmp(&a.abs()).unwrap_or(Ordering::Equal)
+        });
+
+        contributions
+    }
+
+    /// Log-odds of the story being on topic.
+    pub fn score(&self, story: &Story) -> f64 {
+        self.prior
+            + self
+                .contributions(story)
+                .iter()
+                .map(|(_, log_odds)| log_odds)
+                .sum::<f64>()
+    }
+
+    /// Flags stories with positive score. A story is never flagged just
+    /// because most labelled stories were on topic, at least one of its tokens
+    /// must speak for the topic.
+    pub fn reason(&self, story: &Story) -> Option<String> {
+        if self.score(story) <= 0.0 {
+            return None;
+        }
+
+        let tokens: Vec<_> = self
+            .contributions(story)
+            .into_iter()
+            .filter(|(_, log_odds)| *log_odds > 0.0)
+            .take(REASON_TOKENS)
+            .map(|(token, _)| format!("\"{}\"", token))
+            .collect();
+
+        if tokens.is_empty() {
+            None
+        } else {
+            Some(format!("classified by {}", tokens.join(", ")))
+        }
+    }
+
+    /// Reads the model of the topic from `TOPIC_MODELS_DIR`, if it was
+    /// trained.
+    pub fn load(topic: FilterKind) -> Result<Option<Self>> {
+        let path = match settings().topic_models_dir.as_ref() {
+            Some(dir) => path(dir, topic),
+            None => return Ok(None),
+        };
+
+        Self::load_from(&path)
+    }
+
+    /// Writes the model of the topic to `TOPIC_MODELS_DIR` and returns the
+    /// path of the file.
+    pub fn save(&self, topic: FilterKind) -> Result<PathBuf> {
+        let dir = settings()
+            .topic_models_dir
+            .clone()
+            .ok_or("TOPIC_MODELS_DIR must be set to save the model")?;
+        fs::create_dir_all(&dir)?;
+
+        let path = path(&dir, topic);
+        self.save_to(&path)?;
+
+        Ok(path)
+    }
+
+    fn load_from(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+fn path(dir: &Path, topic: FilterKind) -> PathBuf {
+    dir.join(format!("{}.json", topic.name()))
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Trained on {} stories on topic and {} off topic",
+            self.positives, self.negatives
+        )?;
+        writeln!(f, "  known tokens: {}", self.tokens.len())?;
+        writeln!(f, "  prior:        {:+.2}", self.prior)?;
+
+        let mut tokens: Vec<_> = self.tokens.iter().collect();
+        tokens.sort_by(|(_, a), (_, b)| {
+            b.partial_cmp(a).unwrap_or(Ordering::Equal)
+        });
+
+        writeln!(f, "\nMost on topic:")?;
+        for (token, log_odds) in
+            tokens.iter().take(TOP_TOKENS).filter(|(_, l)| **l > 0.0)
+        {
+            writeln!(f, "  {:+.2} {}", log_odds, token)?;
+        }
+
+        writeln!(f, "\nMost off topic:")?;
+        for (token, log_odds) in tokens
+            .iter()
+            .rev()
+            .take(TOP_TOKENS)
+            .filter(|(_, l)| **l < 0.0)
+        {
+            writeln!(f, "  {:+.2} {}", log_odds, token)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Topic {}", self.topic)?;
+        writeln!(f, "  [{}] {}", self.story_id, self.title)?;
+        writeln!(f, "  prior: {:+.2}", self.prior)?;
+        writeln!(f, "  score: {:+.2}", self.score)?;
+        match &self.reason {
+            Some(reason) => writeln!(f, "  flagged, {}", reason)?,
+            None => writeln!(f, "  not flagged")?,
+        }
+
+        writeln!(f, "\nTokens ({}):", self.contributions.len())?;
+        for (token, log_odds) in &self.contributions {
+            writeln!(f, "  {:+.2} {}", log_odds, token)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(title: &str, url: &str) -> Story {
+        let mut story = Story::random_url();
+        story.title = title.to_string();
+        story.kind = StoryKind::Url(url.to_string());
+        story
+    }
+
+    fn examples() -> Vec<(Story, bool)> {
+        vec![
+            (
+                story("Bitcoin hits new high", "https://coindesk.com/a"),
+                true,
+            ),
+            (
+                story("Ethereum and Bitcoin fees", "https://coindesk.com/b"),
+                true,
+            ),
+            (story("The NFT bubble", "https://theverge.com/nft"), true),
+            (
+                story("Rust 1.50 released", "https://blog.rust-lang.org"),
+                false,
+            ),
+            (
+                story("Writing a compiler in Rust", "https://example.com"),
+                false,
+            ),
+            (
+                story("A new high for C++ compilers", "https://example.com"),
+                false,
+            ),
+            (
+                story("How SQLite is tested", "https://sqlite.org/testing"),
+                false,
+            ),
+        ]
+    }
+
+    #[test]
+    fn it_tokenizes_story() {
+        let tokens = tokens(&story(
+            "Show HN: A C++ and C# ORM, in 2 weeks!",
+            "https://www.github.com/x/orm",
+        ));
+        let expected: BTreeSet<_> =
+            ["show", "hn", "c++", "c#", "and", "orm", "in", "weeks"]
+                .iter()
+                .map(|token| token.to_string())
+                .chain(Some("github.com".to_string()))
+                .collect();
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn it_classifies_stories() -> Result<()> {
+        let model = Model::train(&examples())?;
+        assert_eq!(3, model.positives);
+        assert_eq!(4, model.negatives);
+        assert!(model.prior < 0.0);
+        // seen in a single story
+        assert!(!model.tokens.contains_key("nft"));
+        assert!(model.tokens["bitcoin"] > 0.0);
+        assert!(model.tokens["rust"] < 0.0);
+        // as common on topic as off topic
+        assert!(model.tokens["high"].abs() < 0.5);
+
+        let bitcoin = story("Bitcoin crashes", "https://coindesk.com/c");
+        assert_eq!(
+            Some("classified by \"bitcoin\", \"coindesk.com\"".to_string()),
+            model.reason(&bitcoin)
+        );
+        let contributions = model.contributions(&bitcoin);
+        assert_eq!(2, contributions.len());
+        assert!(model.score(&bitcoin) > 0.0);
+
+        let rust = story("Bitcoin node in Rust", "https://example.com");
+        assert!(model.score(&rust) < 0.0);
+        assert_eq!(None, model.reason(&rust));
+
+        // nothing speaks for the topic
+        let unknown = story("Gardening tips", "https://gardening.org");
+        assert!(model.contributions(&unknown).is_empty());
+        assert_eq!(None, model.reason(&unknown));
+
+        assert!(Model::train(&examples()[..3]).is_err());
+        assert!(Model::train(&[]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_records_trainings_in_changelog() -> Result<()> {
+        let model: &'static Model = Box::leak(Box::new(Model {
+            trained_on: vec![
+                "2026-01-02".to_string(),
+                "2026-03-04".to_string(),
+            ],
+            ..Model::train(&examples())?
+        }));
+
+        let days: Vec<_> =
+            model.changelog().iter().map(|change| change.day).collect();
+        assert_eq!(vec!["2026-01-02", "2026-03-04"], days);
+
+        // without a model the topic filter is at its first version
+        assert!(changelog(FilterKind::Crypto).is_empty());
+        assert_eq!(1, FilterKind::Crypto.version());
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_trains_from_labelled_stories() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+        let examples = examples();
+        let stories: Vec<_> = examples
+            .iter()
+            .map(|(story, _)| (story.clone(), vec![]))
+            .collect();
+        db::tests::insert_test_data(&conn, &stories)?;
+
+        for (story, on_topic) in &examples {
+            db::insert_topic_labels(
+                &conn,
+                FilterKind::Crypto,
+                *on_topic,
+                &[story.id],
+            )?;
+        }
+        assert!(train_from_db(&conn, FilterKind::Politics).is_err());
+
+        let model = train_from_db(&conn, FilterKind::Crypto)?;
+        assert_eq!(Model::train(&examples)?.tokens, model.tokens);
+        assert_eq!(vec![date::format_day(date::now())], model.trained_on);
+
+        let path = std::env::temp_dir()
+            .join(format!("sucklesshn-topic-{}.json", rand::random::<u32>()));
+        assert_eq!(None, Model::load_from(&path)?);
+        model.save_to(&path)?;
+        assert_eq!(Some(model), Model::load_from(&path)?);
+        fs::remove_file(&path)?;
+
+        assert_eq!(Some(FilterKind::Crypto), by_name("crypto"));
+        assert_eq!(None, by_name("amfg"));
+
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use reqwest::Client;
+
 use crate::prelude::*;
 
 const FIREBASE_API: &str = "https://hacker-news.firebaseio.com/v0";
@@ -7,23 +9,32 @@ pub fn submission_url(id: StoryId) -> String {
     format!("https://news.ycombinator.com/item?id={}", id)
 }
 
+/// Firebase endpoint with the list of top stories. The same url serves both
+/// the JSON list and the event stream of its changes.
+pub fn top_stories_url() -> String {
+    format!("{}/topstories.json", FIREBASE_API)
+}
+
 /// Polls HN Firebase JSON APIs and grabs [top stories][hn-topstories].
 ///
 /// The stories returned from the APIs are sorted by their position on the HN
 /// front page (ASC).
 ///
 /// [hn-topstories]: https://hacker-news.firebaseio.com/v0/topstories.json
-pub async fn fetch_top_stories() -> Result<Vec<StoryId>> {
+pub async fn fetch_top_stories(client: &Client) -> Result<Vec<StoryId>> {
     let url = format!("{}/topstories.json", FIREBASE_API);
-    let stories: Vec<StoryId> = reqwest::get(&url).await?.json().await?;
+    let stories: Vec<StoryId> = client.get(&url).send().await?.json().await?;
 
     Ok(stories)
 }
 
 /// Given ids, fetches information for all the stories. Calls to HN APIs which
 /// fail are logged and skipped.
-pub async fn fetch_stories(ids: &[StoryId]) -> Result<Vec<Story>> {
-    let jobs = ids.iter().copied().map(fetch_story);
+pub async fn fetch_stories(
+    client: &Client,
+    ids: &[StoryId],
+) -> Result<Vec<Story>> {
+    let jobs = ids.iter().copied().map(|id| fetch_story(client, id));
     let results = futures::future::join_all(jobs).await;
 
     let stories = results
@@ -45,9 +56,9 @@ pub async fn fetch_stories(ids: &[StoryId]) -> Result<Vec<Story>> {
 /// `https://hacker-news.firebaseio.com/v0/item/${STORY_ID}.json`.
 ///
 /// [hn-item]: https://github.com/HackerNews/API#items
-async fn fetch_story(id: StoryId) -> Result<Story> {
+async fn fetch_story(client: &Client, id: StoryId) -> Result<Story> {
     let url = format!("{}/item/{}.json", FIREBASE_API, id);
-    let story = reqwest::get(&url).await?.json().await?;
+    let story = client.get(&url).send().await?.json().await?;
 
     Ok(story)
 }
@@ -58,7 +69,7 @@ mod tests {
 
     #[tokio::test]
     async fn it_fetches_top_stories() -> Result<()> {
-        let stories = fetch_top_stories().await?;
+        let stories = fetch_top_stories(&Client::new()).await?;
         assert_ne!(0, stories.len());
 
         Ok(())
@@ -68,7 +79,7 @@ mod tests {
     async fn it_fetches_ask_hn() -> Result<()> {
         // https://news.ycombinator.com/item?id=23366546
         let story_id = 23366546;
-        let story = fetch_story(story_id).await?;
+        let story = fetch_story(&Client::new(), story_id).await?;
 
         assert_eq!(
             "Ask HN: \
@@ -90,7 +101,7 @@ mod tests {
     async fn it_fetches_url_submission() -> Result<()> {
         // https://news.ycombinator.com/item?id=25300310
         let story_id = 25300310;
-        let story = fetch_story(story_id).await?;
+        let story = fetch_story(&Client::new(), story_id).await?;
 
         assert_eq!("Bit Twiddling Hacks", &story.title);
 
@@ -109,7 +120,8 @@ mod tests {
 
     #[tokio::test]
     async fn it_fetches_stories() -> Result<()> {
-        let stories = fetch_stories(&[25300310, 23366546]).await?;
+        let stories =
+            fetch_stories(&Client::new(), &[25300310, 23366546]).await?;
         assert_eq!(2, stories.len());
         assert_eq!("Bit Twiddling Hacks", &stories[0].title);
         assert_eq!(
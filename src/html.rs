@@ -3,27 +3,78 @@
 //!
 //! [handlebars]: https://handlebarsjs.com/guide/
 
-use {handlebars::Handlebars, serde_json::json};
+use {
+    handlebars::Handlebars,
+    serde::Serialize,
+    serde_json::json,
+    std::{collections::HashMap, time::Duration},
+};
 
-use crate::{filter::Page, prelude::*};
+use crate::{
+    date,
+    filter::{self, Filter, Page},
+    prelude::*,
+    stats::Stats,
+};
 
 // The template handlebars file we use to create each html page.
 const TEMPLATE_CONTENTS: &str =
     include_str!("assets/front-page.handlebars.html");
-
-// Handlebars provides interface for multiple templates, but we don't need this.
 const TEMPLATE_NAME: &str = "front-page";
 
-pub struct Template(Handlebars<'static>);
+// The page with filter statistics, see [`crate::stats`].
+const STATS_TEMPLATE_CONTENTS: &str =
+    include_str!("assets/stats.handlebars.html");
+const STATS_TEMPLATE_NAME: &str = "stats";
+
+// The search page, see [`crate::search`].
+const SEARCH_TEMPLATE_CONTENTS: &str =
+    include_str!("assets/search.handlebars.html");
+const SEARCH_TEMPLATE_NAME: &str = "search";
+
+pub struct Template {
+    handlebars: Handlebars<'static>,
+    // For how long after a filter changed do we show a notice on pages which
+    // use the filter.
+    notice_period: Duration,
+    // When we first ran with each filter version, see
+    // [`crate::db::select_filter_versions`].
+    deployed_at: HashMap<(FilterKind, u32), i64>,
+}
+
+/// Tells users that a filter they use recently changed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FilterNotice {
+    filter: FilterKind,
+    version: u32,
+    day: String,
+    description: &'static str,
+}
 
 impl Template {
-    pub fn new() -> Result<Self> {
+    pub fn new(
+        notice_period: Duration,
+        deployed_at: HashMap<(FilterKind, u32), i64>,
+    ) -> Result<Self> {
         let mut handlebars = Handlebars::new();
 
         handlebars
             .register_template_string(TEMPLATE_NAME, TEMPLATE_CONTENTS)?;
+        handlebars.register_template_string(
+            STATS_TEMPLATE_NAME,
+            STATS_TEMPLATE_CONTENTS,
+        )?;
+        handlebars.register_template_string(
+            SEARCH_TEMPLATE_NAME,
+            SEARCH_TEMPLATE_CONTENTS,
+        )?;
 
-        Ok(Self(handlebars))
+        Ok(Self {
+            handlebars,
+            notice_period,
+            deployed_at,
+        })
     }
 
     /// Given page populated with stories, we render it against the handlebars
@@ -35,12 +86,62 @@ impl Template {
         let json = json!({
             "name": page.name(),
             "stories": page.stories(),
+            "showFilterReasons": page.shows_filter_reasons(),
+            "filterNotices": self.filter_notices(page),
             "dark": dark
         });
 
-        let html = self.0.render(TEMPLATE_NAME, &json)?;
+        let html = self.handlebars.render(TEMPLATE_NAME, &json)?;
         Ok(html)
     }
+
+    /// Renders the filter statistics page.
+    pub fn render_stats(&self, stats: &Stats, theme: Theme) -> Result<String> {
+        let json = json!({
+            "stats": stats,
+            "dark": matches!(theme, Theme::Dark)
+        });
+
+        let html = self.handlebars.render(STATS_TEMPLATE_NAME, &json)?;
+        Ok(html)
+    }
+
+    /// Renders the search page. The stories are loaded by the page itself.
+    pub fn render_search(&self, theme: Theme) -> Result<String> {
+        let json = json!({ "dark": matches!(theme, Theme::Dark) });
+
+        let html = self.handlebars.render(SEARCH_TEMPLATE_NAME, &json)?;
+        Ok(html)
+    }
+
+    // Changes of the page filters which happened within the notice period.
+    fn filter_notices(&self, page: &Page) -> Vec<FilterNotice> {
+        let since = date::now() - self.notice_period.as_secs() as i64;
+
+        page.filters()
+            .flat_map(|filter| {
+                filter.changelog().iter().enumerate().map(
+                    move |(index, change)| {
+                        (filter, filter::version_after(index + 1), change)
+                    },
+                )
+            })
+            .filter_map(|(filter, version, change)| {
+                let changed_at = self
+                    .deployed_at
+                    .get(&(filter, version))
+                    .copied()
+                    .or_else(|| date::parse_day(change.day).ok())?;
+
+                (changed_at >= since).then(|| FilterNotice {
+                    filter,
+                    version,
+                    day: date::format_day(changed_at),
+                    description: change.description,
+                })
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -52,7 +153,7 @@ mod tests {
 
     #[test]
     fn it_should_render_page() -> Result<()> {
-        let engine = Template::new()?;
+        let engine = Template::new(Duration::from_secs(0), HashMap::new())?;
         let conn = db::tests::test_conn()?;
 
         let mut story1 = Story::random_url();
@@ -60,9 +161,23 @@ mod tests {
         story1.title = "Every Model Learned by Gradient Descent".to_string();
         let mut story2 = Story::random_url();
         story2.title = "LinkedIn’s Alternate Universe".to_string();
-        story2.archive_url = Some("https://example.com".to_string());
+        story2.archives = vec![
+            ArchiveLink {
+                provider: "wayback".to_string(),
+                url: "https://example.com/wayback".to_string(),
+                archived_at: Some(1609459200),
+            },
+            ArchiveLink {
+                provider: "archive.today".to_string(),
+                url: "https://example.com/today".to_string(),
+                archived_at: None,
+            },
+        ];
 
-        let stories = &[(story1.clone(), vec![]), (story2.clone(), vec![])];
+        let stories = &[
+            (story1.clone(), vec![FilterKind::Paywall]),
+            (story2.clone(), vec![FilterKind::BigTech]),
+        ];
         let ids = stories.iter().map(|(story, _)| story.id).collect();
         db::tests::insert_test_data(&conn, stories)?;
 
@@ -74,7 +189,17 @@ mod tests {
 
         assert!(dark_html.contains(&story1.title));
         assert!(dark_html.contains(&story2.title));
-        assert!(dark_html.contains(&story2.archive_url.unwrap()));
+        for archive in &story2.archives {
+            assert!(dark_html.contains(&archive.url));
+            assert!(dark_html.contains(&archive.provider));
+        }
+
+        assert!(dark_html.contains(&format!(
+            r#"title="{}""#,
+            db::tests::test_reason(FilterKind::BigTech)
+        )));
+
+        assert_eq!(1, dark_html.matches(r#"class="paywall""#).count());
 
         assert!(dark_html.contains("dark.css"));
         assert!(!dark_html.contains("light.css"));
@@ -83,6 +208,80 @@ mod tests {
         assert!(light_html.contains("light.css"));
         assert!(!light_html.contains("dark.css"));
 
+        // only the page with all stories explains the filters
+        let pages = page::populate(&conn, vec![story2.id], 5);
+        let bigtech_page =
+            pages.into_iter().find(|p| p.name() == "+amfg").unwrap();
+        let html = engine.render(&bigtech_page, Theme::Dark)?;
+        assert!(html.contains(&story2.title));
+        assert!(!html.contains(&db::tests::test_reason(FilterKind::BigTech)));
+
+        // the filter changed outside of the notice period
+        assert!(!html.contains("Filter amfg changed"));
+        let engine = Template::new(
+            Duration::from_secs(u32::MAX as u64),
+            HashMap::new(),
+        )?;
+        let html = engine.render(&bigtech_page, Theme::Dark)?;
+        assert!(html.contains("Filter amfg changed"));
+
+        // the notice period starts when we first ran with the change
+        let deployed_at = date::now() - 60;
+        let engine = Template::new(
+            Duration::from_secs(120),
+            vec![((FilterKind::BigTech, 2), deployed_at)]
+                .into_iter()
+                .collect(),
+        )?;
+        let html = engine.render(&bigtech_page, Theme::Dark)?;
+        assert!(html.contains(&format!(
+            "Filter amfg changed on {} (version 2)",
+            date::format_day(deployed_at)
+        )));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_render_stats() -> Result<()> {
+        let engine = Template::new(Duration::from_secs(0), HashMap::new())?;
+        let conn = db::tests::test_conn()?;
+
+        let mut story = Story::random_url();
+        story.kind = StoryKind::Url("https://bbc.com/news".to_string());
+        db::tests::insert_test_data(
+            &conn,
+            &[(story, vec![FilterKind::LargeNewspaper])],
+        )?;
+        let stats = crate::stats::collect(&conn, 7, date::now())?;
+
+        let html = engine.render_stats(&stats, Theme::Dark)?;
+        assert!(html.contains("1 stories stored"));
+        assert!(html.contains(r#"<a href="/+bignews">bignews</a>"#));
+        assert!(html.contains("bbc.com"));
+        assert!(html.contains("100%"));
+        assert!(html.contains("No story was flagged by two filters"));
+        assert!(html.contains("dark.css"));
+
+        let html = engine.render_stats(&stats, Theme::Light)?;
+        assert!(html.contains("light.css"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_should_render_search() -> Result<()> {
+        let engine = Template::new(Duration::from_secs(0), HashMap::new())?;
+
+        let html = engine.render_search(Theme::Dark)?;
+        assert!(html.contains(r#"fetch("/search.json")"#));
+        assert!(html.contains("${story.id}"));
+        assert!(html.contains("dark.css"));
+        assert!(html.contains(r#"<a href="/light/search">"#));
+
+        let html = engine.render_search(Theme::Light)?;
+        assert!(html.contains("light.css"));
+
         Ok(())
     }
 }
@@ -0,0 +1,32 @@
+//! All outgoing requests (HN APIs, Wayback machine) share a single http client
+//! so that connections are reused and every request is bounded by timeouts.
+//! A hung request would otherwise keep the binary running past the cron job's
+//! deadline.
+
+use reqwest::Client;
+
+use crate::{conf, prelude::*};
+
+/// Creates the http client from the config. The client is cheap to clone as
+/// it's reference counted internally.
+pub fn client(conf: &conf::Conf) -> Result<Client> {
+    let client = Client::builder()
+        .user_agent(&conf.user_agent)
+        .connect_timeout(conf.http_connect_timeout)
+        .timeout(conf.http_timeout)
+        .build()?;
+
+    Ok(client)
+}
+
+/// Creates the http client for long lived responses such as the HN event
+/// stream. Only the connect timeout applies, the response body is expected to
+/// never end.
+pub fn stream_client(conf: &conf::Conf) -> Result<Client> {
+    let client = Client::builder()
+        .user_agent(&conf.user_agent)
+        .connect_timeout(conf.http_connect_timeout)
+        .build()?;
+
+    Ok(client)
+}
@@ -0,0 +1,64 @@
+//! Non-English articles occasionally make it to the front page. We detect the
+//! language offline with [`whatlang`], from the article text if we fetched the
+//! linked page and from the title otherwise.
+//!
+//! Titles are short and the detector often guesses wrong on English titles,
+//! e.g. "Why do we get bored?" is Dutch with low confidence. We therefore only
+//! trust reliable detections of Latin script. Other scripts are never English,
+//! there we take the best guess.
+
+use whatlang::{Lang, Script};
+
+/// ISO 639-3 code of English.
+pub const ENGLISH: &str = "eng";
+
+/// Returns ISO 639-3 code of the language the text is written in, e.g. "deu",
+/// or [`None`] if we cannot tell.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let info = whatlang::detect(text)?;
+
+    if info.is_reliable() || info.script() != Script::Latin {
+        Some(info.lang().code())
+    } else {
+        None
+    }
+}
+
+/// English name of the language with given ISO 639-3 code, e.g. "German" for
+/// "deu".
+pub fn name(code: &str) -> Option<&'static str> {
+    Lang::from_code(code).map(Lang::eng_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_detects_language() {
+        let detected = &[
+            ("Die Geschichte des Internets in Deutschland", "deu"),
+            ("Perché il software è sempre in ritardo", "ita"),
+            ("Le Monde : la France interdit les GAFAM", "fra"),
+            ("プログラミング言語の歴史", "jpn"),
+            ("Почему Rust лучше чем C++", "rus"),
+            (
+                "The Rust programming language helps you write faster, more \
+                reliable software. High-level ergonomics and low-level control \
+                are often at odds in programming language design.",
+                ENGLISH,
+            ),
+        ];
+        for (text, lang) in detected {
+            assert_eq!(Some(*lang), detect(text), "{}", text);
+        }
+
+        // short English titles are often mistaken for other languages
+        for title in &["Why do we get bored?", "Crafting Interpreters", ""] {
+            assert_eq!(None, detect(title), "{}", title);
+        }
+
+        assert_eq!(Some("German"), name("deu"));
+        assert_eq!(None, name("xyz"));
+    }
+}
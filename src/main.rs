@@ -1,71 +1,443 @@
+mod algolia;
 mod archive;
+mod article;
+mod canonical;
+mod cmd;
 mod conf;
+mod date;
 mod db;
+mod domain;
 mod filter;
 mod hn;
 mod html;
+mod http;
+mod lang;
 mod models;
 mod prelude;
+mod search;
+#[cfg(test)]
+mod stand_in;
+mod stats;
+mod stream;
 
-use rusqlite::Connection;
+use {
+    futures::stream::{self as futures_stream, StreamExt},
+    reqwest::Client,
+    rusqlite::Connection,
+    std::{
+        collections::{HashMap, HashSet},
+        env,
+        time::Duration,
+    },
+    tokio::time::{self, Instant},
+};
 
 use {filter::page, prelude::*};
 
+// How long to wait before subscribing again to the top stories stream if the
+// connection dropped.
+const STREAM_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+// How many pages we upload at once. With site pages there can be hundreds of
+// them.
+const UPLOAD_CONCURRENCY: usize = 16;
+
+// The statistics and the search index change slowly, in the streaming mode we
+// upload them at most this often.
+const SUMMARIES_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
     env_logger::init();
     log::info!("--- suckless.hn ---");
 
+    let args: Vec<_> = env::args().skip(1).collect();
+    let command = match cmd::Command::from_args(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            // usage is multiline, debug output of the error would mangle it
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     let conf = conf::Conf::new();
+    filter::init(&conf);
     let conn = db::conn(&conf)?;
+    let client = http::client(&conf)?;
+
+    match command {
+        cmd::Command::Run => (),
+        cmd::Command::ImportAlgolia { from, to } => {
+            log::info!("Importing front page stories from Algolia...");
+            let inserted =
+                algolia::import(&conn, &client, algolia::ALGOLIA_API, from, to)
+                    .await?;
+            log::info!("Imported {} new stories.", inserted);
+            return Ok(());
+        }
+        cmd::Command::EvaluateFilter { filter, keywords } => {
+            let candidate = if keywords.is_empty() {
+                None
+            } else {
+                Some(filter::evaluate::Keywords::new(filter, &keywords))
+            };
+            let report =
+                filter::evaluate::evaluate(&conn, filter, candidate.as_ref())?;
+            println!("{}", report);
+            return Ok(());
+        }
+        cmd::Command::Stats { json } => {
+            let stats = stats::collect(&conn, conf.stats_days, date::now())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("{}", stats);
+            }
+            return Ok(());
+        }
+        cmd::Command::Search { query } => {
+            let results = search::search(&conn, &query, search::LIMIT)?;
+            if results.is_empty() {
+                println!("No stored story matches '{}'", query);
+            }
+            for result in results {
+                println!("{}\n", result);
+            }
+            return Ok(());
+        }
+        cmd::Command::LabelTopic {
+            topic,
+            on_topic,
+            stories,
+        } => {
+            db::insert_topic_labels(&conn, topic, on_topic, &stories)?;
+            log::info!(
+                "Labelled {} stories of topic {}.",
+                stories.len(),
+                topic
+            );
+            return Ok(());
+        }
+        cmd::Command::TrainTopic { topic } => {
+            let model = filter::topic::train_from_db(&conn, topic)?;
+            let path = model.save(topic)?;
+            println!("{}", model);
+            log::info!("Saved model of topic {} to {:?}.", topic, path);
+            return Ok(());
+        }
+        cmd::Command::ExplainTopic { topic, story } => {
+            println!("{}", filter::topic::explain(&conn, topic, story)?);
+            return Ok(());
+        }
+    }
+
+    db::insert_filter_versions(&conn)?;
+    let engine = html::Template::new(
+        conf.filter_notice_period,
+        db::select_filter_versions(&conn)?,
+    )?;
+    if conf.stream_top_stories {
+        return stream_top_stories(&conf, &conn, &client, &engine).await;
+    }
 
     log::info!("Fetching top stories list...");
-    let top_stories = hn::fetch_top_stories().await?;
-    let new_stories =
-        fetch_new_stories(&conn, &top_stories, conf.new_stories_limit).await?;
+    let top_stories = hn::fetch_top_stories(&client).await?;
+    let new_domains = ingest(&conf, &conn, &client, &top_stories).await?;
+
+    log::info!("Generating html pages and uploading to S3...");
+    render(
+        &conf,
+        &conn,
+        &engine,
+        top_stories,
+        &new_domains,
+        &mut HashMap::new(),
+    )
+    .await;
+    upload_summaries(&conf, &conn, &engine).await;
+
+    Ok(())
+}
+
+// Subscribes to the top stories event stream and keeps the pages up to date
+// with HN front page. Never returns, if the stream breaks we subscribe again.
+async fn stream_top_stories(
+    conf: &conf::Conf,
+    conn: &Connection,
+    client: &Client,
+    engine: &html::Template,
+) -> Result<()> {
+    let stream_client = http::stream_client(conf)?;
+    let url = hn::top_stories_url();
+    // the front page changes every few seconds, we don't want to upload pages
+    // which didn't change since the last update
+    let mut rendered = HashMap::new();
+    let mut last_update: Option<Instant> = None;
+    let mut last_summaries_upload: Option<Instant> = None;
+
+    loop {
+        log::info!("Subscribing to top stories stream...");
+        let mut events =
+            match stream::TopStories::connect(&stream_client, &url).await {
+                Ok(events) => events,
+                Err(e) => {
+                    log::error!("Cannot subscribe to top stories: {}", e);
+                    time::sleep(STREAM_RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+        // latest top stories which we haven't processed yet
+        let mut pending = None;
+        loop {
+            let next_update_at =
+                last_update.map(|at| at + conf.stream_min_interval);
+
+            // if there's a pending update, we only wait for the stream until
+            // it's time to process the update
+            let next = match (&pending, next_update_at) {
+                (Some(_), Some(at)) => {
+                    time::timeout_at(at, events.next()).await.ok()
+                }
+                _ => Some(events.next().await),
+            };
+
+            match next {
+                Some(Ok(Some(top_stories))) => pending = Some(top_stories),
+                Some(Ok(None)) => {
+                    log::warn!("Top stories stream closed.");
+                    break;
+                }
+                Some(Err(e)) => {
+                    log::error!("Top stories stream failed: {}", e);
+                    break;
+                }
+                // time for the pending update
+                None => (),
+            };
+
+            let is_due = next_update_at
+                .map(|at| at <= Instant::now())
+                .unwrap_or(true);
+            if let (true, Some(top_stories)) = (is_due, pending.take()) {
+                last_update = Some(Instant::now());
+
+                let new_domains =
+                    match ingest(conf, conn, client, &top_stories).await {
+                        Ok(new_domains) => new_domains,
+                        Err(e) => {
+                            log::error!("Cannot ingest new stories: {}", e);
+                            HashSet::new()
+                        }
+                    };
+                render(
+                    conf,
+                    conn,
+                    engine,
+                    top_stories,
+                    &new_domains,
+                    &mut rendered,
+                )
+                .await;
+
+                let summaries_due = last_summaries_upload
+                    .map(|at| at.elapsed() >= SUMMARIES_INTERVAL)
+                    .unwrap_or(true);
+                if summaries_due {
+                    last_summaries_upload = Some(Instant::now());
+                    upload_summaries(conf, conn, engine).await;
+                }
+            }
+        }
+
+        time::sleep(STREAM_RECONNECT_DELAY).await;
+    }
+}
+
+// Stores stories which we haven't seen yet along with the filters which
+// flagged them. Returns the domains of the stored stories.
+async fn ingest(
+    conf: &conf::Conf,
+    conn: &Connection,
+    client: &Client,
+    top_stories: &[StoryId],
+) -> Result<HashSet<String>> {
+    let wayback = archive::Wayback::new(client.clone(), conf);
+    let providers = archive::Provider::from_conf(&wayback, client, conf);
+    let (new_stories, deferred_archives) =
+        fetch_new_stories(conf, conn, client, &providers, top_stories).await?;
 
     log::info!("Applying Suckless Filters™...");
-    let new_stories_filters = filter::for_stories(&new_stories);
+    let mut new_stories_filters = filter::for_stories(&new_stories);
+    if conf.paywall_detection {
+        log::debug!("Looking for paywalls in linked pages...");
+        filter::paywall::detect(&new_stories, &mut new_stories_filters);
+    }
 
-    db::insert_stories(&conn, new_stories)?;
-    db::insert_filters(&conn, &new_stories_filters)?;
+    log::debug!("Requesting capture of urls without snapshot...");
+    let requested_captures = wayback.save_missing_snapshots(&new_stories).await;
 
-    log::info!("Generating html pages and uploading to S3...");
-    let engine = html::Template::new()?;
-    let pages = page::populate(&conn, top_stories, conf.stories_per_page);
+    let new_stories_ids: Vec<_> =
+        new_stories.iter().map(|story| story.id).collect();
+    let new_domains: HashSet<_> = new_stories
+        .iter()
+        .filter_map(|story| story.domain())
+        .collect();
+    db::insert_stories(conn, new_stories)?;
+    db::insert_filters(conn, &new_stories_filters)?;
+    db::insert_pending_captures(conn, &requested_captures)?;
+    db::defer_archive_check(conn, &deferred_archives)?;
+
+    log::debug!("Refreshing scores of stored top stories...");
+    refresh_scores(conf, conn, client, top_stories, &new_stories_ids).await?;
+
+    log::debug!("Checking captures requested previously...");
+    wayback.check_pending_captures(conn, date::now()).await?;
+
+    log::debug!("Looking again for missing snapshots...");
+    wayback.recheck_missing_snapshots(conn, date::now()).await?;
+
+    Ok(new_domains)
+}
+
+// Points and comments of stories change while they're on the front page and
+// the dynamic filters need them up to date. Stories we've just inserted were
+// fetched moments ago.
+async fn refresh_scores(
+    conf: &conf::Conf,
+    conn: &Connection,
+    client: &Client,
+    top_stories: &[StoryId],
+    new_stories_ids: &[StoryId],
+) -> Result<()> {
+    let ids: Vec<_> = top_stories
+        .iter()
+        .take(conf.score_refresh_limit)
+        .filter(|id| !new_stories_ids.contains(id))
+        .copied()
+        .collect();
+
+    let stories = hn::fetch_stories(client, &ids).await?;
+    db::update_scores(conn, &stories)
+}
 
-    let jobs: Vec<_> = pages
+// Generates html pages and uploads those which changed since the last render.
+// The `rendered` map keeps track of which stories each page listed. Site pages
+// are only generated for the domains of newly stored stories.
+async fn render(
+    conf: &conf::Conf,
+    conn: &Connection,
+    engine: &html::Template,
+    top_stories: Vec<StoryId>,
+    new_domains: &HashSet<String>,
+    rendered: &mut HashMap<String, Vec<StoryId>>,
+) {
+    let mut pages = page::populate(conn, top_stories, conf.stories_per_page);
+    if let Some(min_stories) = conf.site_pages_min_stories {
+        pages.extend(page::populate_sites(
+            conn,
+            new_domains,
+            min_stories,
+            conf.stories_per_page,
+        ));
+    }
+
+    let pages: Vec<_> = pages
         .into_iter()
-        .map(|page| page.upload(&conf, &engine))
+        .filter(|page| {
+            let story_ids = page.story_ids();
+            if rendered.get(page.name()) == Some(&story_ids) {
+                false
+            } else {
+                rendered.insert(page.name().to_string(), story_ids);
+                true
+            }
+        })
         .collect();
-    let results: Vec<Result<()>> = futures::future::join_all(jobs).await;
+    log::debug!("Uploading {} changed pages...", pages.len());
+
+    let results: Vec<(String, Result<()>)> = futures_stream::iter(pages)
+        .map(|page| async move {
+            let name = page.name().to_string();
+            (name, page.upload(conf, engine).await)
+        })
+        .buffer_unordered(UPLOAD_CONCURRENCY)
+        .collect()
+        .await;
 
-    for error in results.into_iter().filter_map(|r| r.err()) {
-        log::error!("Cannot upload page: {}", error);
+    for (name, result) in results {
+        if let Err(error) = result {
+            log::error!("Cannot upload page {}: {}", name, error);
+            // try again next time
+            rendered.remove(&name);
+        }
     }
+}
 
-    Ok(())
+// Uploads the filter statistics, see [`stats`], and the search page if
+// enabled, see [`search`]. Failures are only logged, these pages aren't worth
+// failing the run.
+async fn upload_summaries(
+    conf: &conf::Conf,
+    conn: &Connection,
+    engine: &html::Template,
+) {
+    let result = match stats::collect(conn, conf.stats_days, date::now()) {
+        Ok(stats) => stats::upload(conf, engine, &stats).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        log::error!("Cannot upload stats: {}", e);
+    }
+
+    if conf.search_page {
+        if let Err(e) = search::upload(conf, conn, engine).await {
+            log::error!("Cannot upload search page: {}", e);
+        }
+    }
 }
 
-// Puts together hn fetching, db queries and archive fetching.
+// Puts together hn fetching, db queries, archive and article fetching. Also
+// returns ids of stories whose archives weren't looked for within the time
+// budget.
 async fn fetch_new_stories(
+    conf: &conf::Conf,
     conn: &Connection,
+    client: &Client,
+    archive_providers: &[archive::Provider],
     top_stories: &[StoryId],
-    new_stories_limit: usize,
-) -> Result<Vec<Story>> {
+) -> Result<(Vec<Story>, Vec<StoryId>)> {
     log::debug!(
         "Checking how many out of the {} top stories are already stored.",
         top_stories.len()
     );
     let mut new_stories_ids = db::only_new_stories(conn, top_stories)?;
-    new_stories_ids.truncate(new_stories_limit);
+    new_stories_ids.truncate(conf.new_stories_limit);
 
     log::debug!("Fetching {} new stories...", new_stories_ids.len());
-    let mut stories = hn::fetch_stories(&new_stories_ids).await?;
+    let mut stories = hn::fetch_stories(client, &new_stories_ids).await?;
 
     log::debug!("Fetching snapshots for new stories...");
-    archive::fetch_snapshots_for_stories(&mut stories).await?;
+    let deferred =
+        archive::fetch_archives_for_stories(archive_providers, &mut stories)
+            .await;
+    if !deferred.is_empty() {
+        log::info!("Deferring snapshots of {} stories.", deferred.len());
+    }
+
+    // paywall markers are looked for in the fetched pages
+    if conf.fetch_articles || conf.paywall_detection {
+        log::debug!("Fetching linked articles of new stories...");
+        article::fetch_for_stories(
+            client,
+            &mut stories,
+            conf.article_max_bytes,
+            conf.article_timeout,
+        )
+        .await;
+    }
 
-    Ok(stories)
+    Ok((stories, deferred))
 }
@@ -3,7 +3,10 @@ use {
     std::{borrow::Cow, cmp::Ordering, fmt, result},
 };
 
-use {super::*, crate::filter::Filter};
+use {
+    super::*,
+    crate::{domain, filter::Filter, lang},
+};
 
 impl Modifier {
     fn inner_filter(&self) -> FilterKind {
@@ -110,3 +113,22 @@ impl fmt::Display for Theme {
         write!(f, "{}", self.as_str())
     }
 }
+
+impl Story {
+    /// Registrable domain of the story url, text submissions have none.
+    pub fn domain(&self) -> Option<String> {
+        match &self.kind {
+            StoryKind::Url(url) => domain::registrable(url),
+            StoryKind::Text(_) => None,
+        }
+    }
+
+    /// ISO 639-3 code of the language of the story, see [`crate::lang`]. The
+    /// article text tells more than the title, if we fetched it.
+    pub fn lang(&self) -> Option<&'static str> {
+        self.article
+            .as_ref()
+            .and_then(|article| lang::detect(&article.text))
+            .or_else(|| lang::detect(&self.title))
+    }
+}
@@ -1,14 +1,17 @@
 pub mod impls;
 
+// re-exports whatever public helpers the impls grow
+#[allow(unused_imports)]
 pub use impls::*;
 
 use {
     serde::{Deserialize, Serialize},
-    std::collections::HashSet,
+    std::collections::{BTreeMap, HashSet},
 };
 
 pub type StoryId = i64;
-pub type StoryFilters = (StoryId, Vec<FilterKind>);
+/// Filters which flagged a story, each with a reason why.
+pub type StoryFilters = (StoryId, Vec<(FilterKind, String)>);
 
 /// Supported filters, for specifics see [`filter::impls`] module.
 #[derive(Copy, Clone, Eq, Hash, PartialEq)]
@@ -17,6 +20,21 @@ pub enum FilterKind {
     ShowHn,
     LargeNewspaper,
     BigTech,
+    OldContent,
+    Pdf,
+    Video,
+    Audio,
+    Paywall,
+    TellHn,
+    LaunchHn,
+    Hiring,
+    Flamewar,
+    LowSignal,
+    LongRead,
+    NonEnglish,
+    AiHype,
+    Crypto,
+    Politics,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -25,8 +43,22 @@ pub enum FilterKind {
 pub struct Story {
     pub id: StoryId,
     pub title: String,
-    /// Optional url to wayback machine.
-    pub archive_url: Option<String>,
+    /// Unix time of the submission on HN.
+    #[serde(rename = "time")]
+    pub submitted_at: Option<i64>,
+    /// Points of the story when we fetched it, changes over time.
+    #[serde(default)]
+    pub score: Option<i64>,
+    /// Number of comments when we fetched the story, changes over time.
+    #[serde(default, rename = "descendants")]
+    pub comments: Option<i64>,
+    /// Links to archived versions of the story url, at most one per archive
+    /// provider. HN API doesn't know about these.
+    #[serde(skip)]
+    pub archives: Vec<ArchiveLink>,
+    /// What we extracted from the linked page, if we fetched it.
+    #[serde(skip)]
+    pub article: Option<Article>,
     /// Flattening the kind allows us to use enum instead of two mutually
     /// exclusive options.
     #[serde(flatten)]
@@ -43,6 +75,26 @@ pub enum StoryKind {
     Text(String),
 }
 
+/// Information about the linked page of a story, see [`crate::article`].
+#[derive(Debug, Default)]
+#[cfg_attr(test, derive(Clone, PartialEq))]
+pub struct Article {
+    /// Content of the `<title>` element.
+    pub title: Option<String>,
+    /// The description meta tag, or the Open Graph one.
+    pub description: Option<String>,
+    /// Lowercase primary language subtag declared by the page, e.g. "en".
+    pub lang: Option<String>,
+    /// Number of words in the readable text of the whole page.
+    pub word_count: usize,
+    /// Readable text of the page without markup, truncated.
+    pub text: String,
+    /// Label of the paywall marker found in the page, see
+    /// [`crate::filter::paywall`]. It isn't stored, only new stories are
+    /// checked.
+    pub paywall_marker: Option<&'static str>,
+}
+
 /// Story information which we retrieve from the database. A join query on both
 /// `stories` and `story_filters` tables.
 #[derive(Serialize, Debug)]
@@ -51,8 +103,99 @@ pub struct StoryWithFilters {
     pub id: StoryId,
     pub title: String,
     pub url: String,
-    pub archive_url: Option<String>,
+    /// See [`crate::canonical`], stories with the same canonical url are
+    /// reposts of each other.
+    pub canonical_url: String,
+    /// See [`crate::domain`], text submissions have none.
+    pub domain: Option<String>,
+    /// Unix time of the submission on HN, if we know it.
+    pub submitted_at: Option<i64>,
+    /// ISO 639-3 code of the language, see [`crate::lang`]. Stories inserted
+    /// by older versions have none.
+    pub lang: Option<String>,
+    /// Points and number of comments as of the last refresh, see
+    /// [`crate::db::update_scores`].
+    pub score: Option<i64>,
+    pub comments: Option<i64>,
+    pub archives: Vec<ArchiveLink>,
     pub filters: HashSet<FilterKind>,
+    /// Why the filters flagged the story. Stories inserted by older versions
+    /// have no reasons recorded.
+    pub filter_reasons: BTreeMap<FilterKind, String>,
+    /// Flagged by [`FilterKind::Paywall`], the page shows a badge next to the
+    /// archive links.
+    pub paywalled: bool,
+    /// Stories we store which link to the same content and were submitted
+    /// before this one, most recent first.
+    pub earlier_submissions: Vec<EarlierSubmission>,
+}
+
+/// Link to the discussion of an earlier submission of the same content.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct EarlierSubmission {
+    pub id: StoryId,
+    pub title: String,
+    /// Link to the HN discussion.
+    pub url: String,
+    /// Day of the submission as `YYYY-MM-DD`, if we know it.
+    pub submitted_on: Option<String>,
+}
+
+/// Archived version of a story url as found by one of the archive providers.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(test, derive(Clone, PartialEq))]
+pub struct ArchiveLink {
+    /// Name of the provider, e.g. "wayback", which is also the link label.
+    pub provider: String,
+    pub url: String,
+    /// Unix time of when the snapshot was taken, if the provider tells us.
+    pub archived_at: Option<i64>,
+}
+
+/// Flags of a stored story, the input of [`crate::stats`].
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct FlaggedStory {
+    pub id: StoryId,
+    /// Unix time of when we stored the story.
+    pub created_at: i64,
+    /// See [`crate::domain`], text submissions have none.
+    pub domain: Option<String>,
+    /// Filters which flagged the story ordered as [`crate::filter::FILTERS`],
+    /// each with the reason unless the story was inserted by an older version.
+    pub filters: Vec<(FilterKind, Option<String>)>,
+}
+
+/// Stored story found by a full-text search, see [`crate::search`].
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub id: StoryId,
+    pub title: String,
+    pub url: String,
+    /// See [`crate::domain`], text submissions have none.
+    pub domain: Option<String>,
+    /// Unix time of the submission on HN, if we know it.
+    pub submitted_at: Option<i64>,
+    /// Part of the title or the article text around the matched words, which
+    /// are in square brackets. The search index has none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// Url which we asked an archive to capture, but which didn't have a snapshot
+/// when we last checked.
+#[derive(Debug, PartialEq)]
+pub struct PendingCapture {
+    pub story_id: StoryId,
+    pub url: String,
+    /// Unix time of when we asked for the capture.
+    pub requested_at: i64,
+    /// How many times we checked whether the snapshot is available.
+    pub checks: i64,
 }
 
 /// Determines whether we are interested in stories matching or not matching
@@ -83,16 +226,26 @@ mod tests {
             let Story {
                 id,
                 title,
-                archive_url,
+                archives,
                 ..
             } = Story::random_url();
 
+            let url = random_url();
             Self {
                 id,
                 title,
-                url: random_url(),
-                archive_url,
+                canonical_url: crate::canonical::url(&url),
+                domain: crate::domain::registrable(&url),
+                url,
+                submitted_at: None,
+                lang: None,
+                score: None,
+                comments: None,
+                archives,
+                paywalled: filters.contains(&FilterKind::Paywall),
                 filters: filters.into_iter().collect(),
+                filter_reasons: BTreeMap::new(),
+                earlier_submissions: vec![],
             }
         }
     }
@@ -103,7 +256,11 @@ mod tests {
             Self {
                 id: random::<i64>().abs(),
                 title: gen.next().unwrap(),
-                archive_url: None,
+                submitted_at: None,
+                score: None,
+                comments: None,
+                archives: vec![],
+                article: None,
                 kind: StoryKind::Url(random_url()),
             }
         }
@@ -113,7 +270,11 @@ mod tests {
             Self {
                 id: random::<i64>().abs(),
                 title: gen.next().unwrap(),
-                archive_url: None,
+                submitted_at: None,
+                score: None,
+                comments: None,
+                archives: vec![],
+                article: None,
                 kind: StoryKind::Text(gen.next().unwrap()),
             }
         }
@@ -0,0 +1,189 @@
+//! Every story we ever stored is searchable by its title and, if we fetched the
+//! linked page, by the beginning of the article text. The index is a sqlite
+//! [FTS5][fts5] table kept in sync on insert, see [`crate::db`].
+//!
+//! Optionally we also upload a static `search` page. There's no server behind
+//! the site, hence the page downloads `search.json` with the titles of all
+//! stored stories and searches them in the browser. The index only grows with
+//! new stories, so we upload it again only once the number of stored stories
+//! changes.
+//!
+//! [fts5]: https://www.sqlite.org/fts5.html
+
+use {rusqlite::Connection, std::fmt};
+
+use crate::{conf, date, db, filter::page, hn, html::Template, prelude::*};
+
+/// Object path of the index which the search page downloads.
+const INDEX_OBJECT: &str = "search.json";
+
+/// How many stories the search command lists at most.
+pub const LIMIT: usize = 20;
+
+/// Stored stories matching all words of the query, the best match first. A
+/// word ending with "*" matches any word with that prefix.
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchResult>> {
+    match match_query(query) {
+        Some(query) => db::search(conn, &query, limit),
+        None => Ok(vec![]),
+    }
+}
+
+/// Uploads the search page in both themes and the index of all stored stories
+/// which it searches.
+pub async fn upload(
+    conf: &conf::Conf,
+    conn: &Connection,
+    engine: &Template,
+) -> Result<()> {
+    let story_count = db::count_stories(conn)?;
+    if db::select_upload_story_count(conn, INDEX_OBJECT)? == Some(story_count) {
+        log::debug!("Search index of {} stories is up to date", story_count);
+    } else {
+        let index = db::select_search_index(conn)?;
+        let json = serde_json::to_string(&index)?;
+        page::put_object(
+            conf,
+            &format!("pages/{}", INDEX_OBJECT),
+            INDEX_OBJECT,
+            json.as_bytes(),
+            "application/json",
+        )
+        .await?;
+        db::insert_upload(conn, INDEX_OBJECT, index.len())?;
+    }
+
+    for theme in [Theme::Dark, Theme::Light].iter().copied() {
+        let html = engine.render_search(theme)?;
+        page::put_object(
+            conf,
+            &format!("pages/{}/search.html", theme),
+            &theme.object_path("search"),
+            html.as_bytes(),
+            "text/html",
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// FTS5 has its own query syntax in which e.g. "C++" or "don't" are errors. We
+// quote each word so that users don't need to know it.
+fn match_query(query: &str) -> Option<String> {
+    let terms: Vec<_> = query
+        .split_whitespace()
+        .filter_map(|word| {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(word) => (word, "*"),
+                None => (word, ""),
+            };
+
+            if word.is_empty() {
+                None
+            } else {
+                Some(format!("\"{}\"{}", word.replace('"', "\"\""), prefix))
+            }
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+impl fmt::Display for SearchResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.id, self.title)?;
+        let submitted_on = self.submitted_at.map(date::format_day);
+        match (&self.domain, submitted_on) {
+            (Some(domain), Some(day)) => writeln!(f, " ({}, {})", domain, day)?,
+            (Some(domain), None) => writeln!(f, " ({})", domain)?,
+            (None, Some(day)) => writeln!(f, " ({})", day)?,
+            (None, None) => writeln!(f)?,
+        }
+
+        if let Some(snippet) = &self.snippet {
+            writeln!(f, "    {}", snippet)?;
+        }
+        writeln!(f, "    {}", self.url)?;
+        write!(f, "    {}", hn::submission_url(self.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_match_query() {
+        assert_eq!(
+            Some(r#""rust" "C++" "don't""#.to_string()),
+            match_query("rust  C++ don't")
+        );
+        assert_eq!(
+            Some(r#""wasm"* "say""hi""""#.to_string()),
+            match_query(r#"wasm* say"hi""#)
+        );
+        assert_eq!(None, match_query(" * "));
+        assert_eq!(None, match_query(""));
+    }
+
+    #[test]
+    fn it_searches_stories() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+
+        let mut rust = Story::random_url();
+        rust.title = "Rust 2.0 released".to_string();
+        let mut article = Story::random_url();
+        article.title = "Show HN: My side project".to_string();
+        article.article = Some(Article {
+            text: "A tiny WebAssembly runtime written in Rust, with a \
+                garbage collector."
+                .to_string(),
+            ..Default::default()
+        });
+        let mut cafe = Story::random_text();
+        cafe.title = "Ask HN: Best café for remote work?".to_string();
+        db::tests::insert_test_data(
+            &conn,
+            &[(rust, vec![]), (article, vec![]), (cafe, vec![])],
+        )?;
+
+        let titles = |query: &str| -> Result<Vec<String>> {
+            let results = search(&conn, query, LIMIT)?;
+            Ok(results.into_iter().map(|result| result.title).collect())
+        };
+
+        // the title match ranks higher
+        assert_eq!(
+            vec!["Rust 2.0 released", "Show HN: My side project"],
+            titles("rust")?
+        );
+        assert_eq!(vec!["Show HN: My side project"], titles("garbage rust")?);
+        assert_eq!(vec!["Show HN: My side project"], titles("webassem*")?);
+        // diacritics and case don't matter
+        assert_eq!(vec!["Ask HN: Best café for remote work?"], titles("CAFE")?);
+        assert!(titles("rust python")?.is_empty());
+        assert!(titles("C++ \"")?.is_empty());
+        assert!(titles("")?.is_empty());
+
+        let results = search(&conn, "collector", LIMIT)?;
+        assert_eq!(
+            Some(
+                "A tiny WebAssembly runtime written in Rust, with a garbage \
+                [collector]."
+            ),
+            results[0].snippet.as_deref()
+        );
+        assert!(results[0].to_string().contains("[collector]"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+//! Tiny http server which tests use in place of the remote APIs. Each
+//! connection is answered by the handler and closed, which is all that our
+//! clients need.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// What the stand-in has been asked for.
+#[derive(Clone, Debug)]
+pub struct Request {
+    /// Path including the query string.
+    pub path: String,
+}
+
+/// What the stand-in answers with.
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+pub struct StandIn {
+    /// Base url without trailing slash, e.g. "http://127.0.0.1:1234".
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl Response {
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self::status(200, body)
+    }
+
+    pub fn status(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: body.into(),
+        }
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl ToString) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+}
+
+impl StandIn {
+    /// Binds to a random local port and answers every request with the
+    /// handler in a background thread.
+    pub fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&Request) -> Response + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => continue,
+                };
+                let request = match read_request(&mut stream) {
+                    Some(request) => request,
+                    None => continue,
+                };
+                let response = handler(&request);
+                log.lock().unwrap().push(request);
+
+                let mut head = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\n\
+                    Connection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in &response.headers {
+                    head.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                head.push_str("\r\n");
+
+                stream.write_all(head.as_bytes()).ok();
+                stream.write_all(response.body.as_bytes()).ok();
+                stream.flush().ok();
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// All requests received so far in order.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let path = request_line.split_whitespace().nth(1)?.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    // the body is not of interest, but it must be read before responding
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(Request { path })
+}
@@ -0,0 +1,550 @@
+//! How much does each filter remove? We look at the stories stored within the
+//! last few days and count the flags of each filter by day and by week, how
+//! often two filters flag the same story, and which domains and reasons are
+//! behind the flags. The statistics are printed by the `stats` command and
+//! uploaded as the `stats` page and as `stats.json`.
+//!
+//! Only the flags stored in the database count. The dynamic filters, see
+//! [`crate::filter::DYNAMIC_FILTERS`], flag stories when pages are rendered
+//! and don't have any stored.
+
+use {
+    rusqlite::Connection,
+    serde::Serialize,
+    std::{collections::HashMap, fmt},
+};
+
+use crate::{
+    conf,
+    date::{self, SECS_IN_DAY},
+    db,
+    filter::{self, page, Filter},
+    html::Template,
+    prelude::*,
+};
+
+// How many domains and reasons we list with each filter.
+const TOP_COUNTS: usize = 5;
+
+// How many pairs of filters we list, the most overlapping first.
+const MAX_OVERLAPS: usize = 20;
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    /// The first day of the statistics as `YYYY-MM-DD`.
+    pub since: String,
+    /// The last day of the statistics, today when generated.
+    pub until: String,
+    /// How many stories we stored within the days.
+    pub stories: usize,
+    /// One entry per filter ordered as [`filter::FILTERS`].
+    pub filters: Vec<FilterStats>,
+    /// Flag rates of each day, oldest first.
+    pub daily: Vec<Period>,
+    /// Flag rates of each week starting on Monday, oldest first. The first
+    /// week is cut by the first day.
+    pub weekly: Vec<Period>,
+    /// Pairs of filters which flagged the same stories, the most such
+    /// stories first.
+    pub overlaps: Vec<Overlap>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterStats {
+    pub filter: FilterKind,
+    pub flagged: usize,
+    /// Percentage of all stories the filter flagged.
+    pub percent: f64,
+    /// Domains of the flagged stories, the most frequent first.
+    pub top_domains: Vec<Count>,
+    /// Reasons of the flags, e.g. which keyword matched, the most frequent
+    /// first.
+    pub top_reasons: Vec<Count>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Count {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Period {
+    /// The first day of the period as `YYYY-MM-DD`.
+    pub start: String,
+    pub stories: usize,
+    /// One entry per filter ordered as [`filter::FILTERS`].
+    pub rates: Vec<Rate>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Rate {
+    pub filter: FilterKind,
+    pub flagged: usize,
+    /// Percentage of the stories of the period the filter flagged.
+    pub percent: f64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Overlap {
+    pub filters: [FilterKind; 2],
+    /// How many stories both filters flagged.
+    pub stories: usize,
+    /// Percentage of the stories of the less frequent filter which the other
+    /// filter flagged too.
+    pub percent: f64,
+}
+
+/// Statistics of the stories stored within the last `days` days up to `now`,
+/// both given as unix time.
+pub fn collect(conn: &Connection, days: usize, now: i64) -> Result<Stats> {
+    let today = now.div_euclid(SECS_IN_DAY);
+    let since = (today - days as i64 + 1) * SECS_IN_DAY;
+    let stories = db::select_flagged_stories(conn, since)?;
+
+    Ok(compute(&stories, since, now))
+}
+
+/// Uploads the statistics page in both themes and the statistics as json.
+pub async fn upload(
+    conf: &conf::Conf,
+    engine: &Template,
+    stats: &Stats,
+) -> Result<()> {
+    let json = serde_json::to_string(stats)?;
+    page::put_object(
+        conf,
+        "pages/stats.json",
+        "stats.json",
+        json.as_bytes(),
+        "application/json",
+    )
+    .await?;
+
+    for theme in [Theme::Dark, Theme::Light].iter().copied() {
+        let html = engine.render_stats(stats, theme)?;
+        page::put_object(
+            conf,
+            &format!("pages/{}/stats.html", theme),
+            &theme.object_path("stats"),
+            html.as_bytes(),
+            "text/html",
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn compute(stories: &[FlaggedStory], since: i64, now: i64) -> Stats {
+    let first_day = since.div_euclid(SECS_IN_DAY);
+    let last_day = now.div_euclid(SECS_IN_DAY);
+    let day_of =
+        |story: &FlaggedStory| story.created_at.div_euclid(SECS_IN_DAY);
+
+    let daily = (first_day..=last_day)
+        .map(|day| {
+            let stories = stories.iter().filter(|story| day_of(story) == day);
+            Period::new(day, stories)
+        })
+        .collect();
+
+    let weekly = (week_of(first_day)..=week_of(last_day))
+        .step_by(7)
+        .map(|week| {
+            let stories = stories
+                .iter()
+                .filter(|story| week_of(day_of(story)) == week);
+            Period::new(week.max(first_day), stories)
+        })
+        .collect();
+
+    let filters = filter::FILTERS
+        .iter()
+        .map(|filter| {
+            let flagged: Vec<_> = stories
+                .iter()
+                .filter_map(|story| {
+                    let (_, reason) =
+                        story.filters.iter().find(|(f, _)| f == filter)?;
+                    Some((story, reason))
+                })
+                .collect();
+
+            FilterStats {
+                filter: *filter,
+                flagged: flagged.len(),
+                percent: percent(flagged.len(), stories.len()),
+                top_domains: top_counts(
+                    flagged
+                        .iter()
+                        .filter_map(|(story, _)| story.domain.as_deref()),
+                ),
+                top_reasons: top_counts(
+                    flagged.iter().filter_map(|(_, reason)| reason.as_deref()),
+                ),
+            }
+        })
+        .collect();
+
+    Stats {
+        since: date::format_day(since),
+        until: date::format_day(now),
+        stories: stories.len(),
+        filters,
+        daily,
+        weekly,
+        overlaps: overlaps(stories),
+    }
+}
+
+impl Period {
+    fn new<'a>(
+        day: i64,
+        stories: impl Iterator<Item = &'a FlaggedStory>,
+    ) -> Self {
+        let stories: Vec<_> = stories.collect();
+        let rates = filter::FILTERS
+            .iter()
+            .map(|filter| {
+                let flagged = stories
+                    .iter()
+                    .filter(|story| {
+                        story.filters.iter().any(|(f, _)| f == filter)
+                    })
+                    .count();
+
+                Rate {
+                    filter: *filter,
+                    flagged,
+                    percent: percent(flagged, stories.len()),
+                }
+            })
+            .collect();
+
+        Self {
+            start: date::format_day(day * SECS_IN_DAY),
+            stories: stories.len(),
+            rates,
+        }
+    }
+}
+
+// Counts of each pair of filters which flagged the same story.
+fn overlaps(stories: &[FlaggedStory]) -> Vec<Overlap> {
+    let mut flagged: HashMap<FilterKind, usize> = HashMap::new();
+    let mut both: HashMap<(usize, usize), usize> = HashMap::new();
+    for story in stories {
+        let indices: Vec<_> = filter::FILTERS
+            .iter()
+            .enumerate()
+            .filter(|(_, filter)| {
+                story.filters.iter().any(|(f, _)| f == *filter)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        for (position, a) in indices.iter().enumerate() {
+            *flagged.entry(filter::FILTERS[*a]).or_default() += 1;
+            for b in &indices[position + 1..] {
+                *both.entry((*a, *b)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut overlaps: Vec<_> = both
+        .into_iter()
+        .map(|((a, b), stories)| {
+            let filters = [filter::FILTERS[a], filter::FILTERS[b]];
+            let smaller = flagged[&filters[0]].min(flagged[&filters[1]]);
+
+            Overlap {
+                filters,
+                stories,
+                percent: percent(stories, smaller),
+            }
+        })
+        .collect();
+    overlaps.sort_by(|a, b| {
+        b.stories.cmp(&a.stories).then_with(|| {
+            let names =
+                |o: &Overlap| (o.filters[0].name(), o.filters[1].name());
+            names(a).cmp(&names(b))
+        })
+    });
+    overlaps.truncate(MAX_OVERLAPS);
+
+    overlaps
+}
+
+// Day of the Monday of the week of the day, both as days since 1970-01-01
+// which was a Thursday.
+fn week_of(day: i64) -> i64 {
+    day - (day + 3).rem_euclid(7)
+}
+
+// The most frequent first, ties by name.
+fn top_counts<'a>(names: impl Iterator<Item = &'a str>) -> Vec<Count> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for name in names {
+        *counts.entry(name).or_default() += 1;
+    }
+
+    let mut counts: Vec<_> = counts
+        .into_iter()
+        .map(|(name, count)| Count {
+            name: name.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.name.cmp(&b.name)));
+    counts.truncate(TOP_COUNTS);
+
+    counts
+}
+
+// Rounded to one decimal place.
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 * 1000.0 / whole as f64).round() / 10.0
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} stories stored from {} to {}",
+            self.stories, self.since, self.until
+        )?;
+
+        writeln!(f, "\nFilters:")?;
+        for stats in &self.filters {
+            writeln!(
+                f,
+                "  {:<12} {:>5} ({}%)",
+                stats.filter.name(),
+                stats.flagged,
+                stats.percent
+            )?;
+            if !stats.top_domains.is_empty() {
+                writeln!(f, "    domains: {}", join(&stats.top_domains))?;
+            }
+            if !stats.top_reasons.is_empty() {
+                writeln!(f, "    reasons: {}", join(&stats.top_reasons))?;
+            }
+        }
+
+        writeln!(f, "\nWeekly:")?;
+        for period in &self.weekly {
+            writeln!(f, "  {}", period)?;
+        }
+
+        writeln!(f, "\nDaily:")?;
+        for period in &self.daily {
+            writeln!(f, "  {}", period)?;
+        }
+
+        writeln!(f, "\nOverlaps:")?;
+        for overlap in &self.overlaps {
+            writeln!(
+                f,
+                "  {} + {}: {} stories ({}% of the smaller)",
+                overlap.filters[0],
+                overlap.filters[1],
+                overlap.stories,
+                overlap.percent
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Only the filters which flagged anything in the period are listed.
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rates: Vec<_> = self
+            .rates
+            .iter()
+            .filter(|rate| rate.flagged > 0)
+            .map(|rate| format!("{} {}%", rate.filter, rate.percent))
+            .collect();
+
+        write!(
+            f,
+            "{} ({} stories): {}",
+            self.start,
+            self.stories,
+            rates.join(", ")
+        )
+    }
+}
+
+fn join(counts: &[Count]) -> String {
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|count| format!("{} ({})", count.name, count.count))
+        .collect();
+
+    counts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged(
+        id: StoryId,
+        created_at: i64,
+        domain: &str,
+        filters: &[(FilterKind, &str)],
+    ) -> FlaggedStory {
+        FlaggedStory {
+            id,
+            created_at,
+            domain: Some(domain.to_string()),
+            filters: filters
+                .iter()
+                .map(|(filter, reason)| (*filter, Some(reason.to_string())))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn it_computes_stats() {
+        let monday = date::parse_day("2021-01-04").unwrap();
+        let google = (FilterKind::BigTech, "mentions google");
+        let bbc = (FilterKind::LargeNewspaper, "links to bbc.com");
+        let stories = [
+            flagged(1, monday - SECS_IN_DAY, "bbc.com", &[google, bbc]),
+            flagged(2, monday + 10, "bbc.com", &[bbc]),
+            flagged(3, monday + 20, "bbc.com", &[google, bbc]),
+            flagged(4, monday + 30, "github.com", &[]),
+            flagged(5, monday + SECS_IN_DAY, "google.com", &[google]),
+        ];
+
+        let since = monday - 2 * SECS_IN_DAY;
+        let stats = compute(&stories, since, monday + SECS_IN_DAY + 100);
+        assert_eq!("2021-01-02", stats.since);
+        assert_eq!("2021-01-05", stats.until);
+        assert_eq!(5, stats.stories);
+
+        let bigtech = &stats.filters[filter::FILTERS
+            .iter()
+            .position(|f| *f == FilterKind::BigTech)
+            .unwrap()];
+        assert_eq!(3, bigtech.flagged);
+        assert_eq!(60.0, bigtech.percent);
+        assert_eq!(
+            vec![
+                Count {
+                    name: "bbc.com".to_string(),
+                    count: 2
+                },
+                Count {
+                    name: "google.com".to_string(),
+                    count: 1
+                }
+            ],
+            bigtech.top_domains
+        );
+        assert_eq!(
+            vec![Count {
+                name: "mentions google".to_string(),
+                count: 3
+            }],
+            bigtech.top_reasons
+        );
+
+        let days: Vec<_> = stats
+            .daily
+            .iter()
+            .map(|period| (period.start.as_str(), period.stories))
+            .collect();
+        assert_eq!(
+            vec![
+                ("2021-01-02", 0),
+                ("2021-01-03", 1),
+                ("2021-01-04", 3),
+                ("2021-01-05", 1)
+            ],
+            days
+        );
+        let rate = |period: &Period, filter| {
+            let rate = period.rates.iter().find(|r| r.filter == filter);
+            rate.map(|rate| rate.percent).unwrap()
+        };
+        assert_eq!(0.0, rate(&stats.daily[0], FilterKind::BigTech));
+        assert_eq!(33.3, rate(&stats.daily[2], FilterKind::BigTech));
+        assert_eq!(66.7, rate(&stats.daily[2], FilterKind::LargeNewspaper));
+
+        // the first week is cut by the first day
+        let weeks: Vec<_> = stats
+            .weekly
+            .iter()
+            .map(|period| (period.start.as_str(), period.stories))
+            .collect();
+        assert_eq!(vec![("2021-01-02", 1), ("2021-01-04", 4)], weeks);
+        assert_eq!(50.0, rate(&stats.weekly[1], FilterKind::BigTech));
+
+        assert_eq!(1, stats.overlaps.len());
+        let overlap = &stats.overlaps[0];
+        assert_eq!(
+            [FilterKind::BigTech, FilterKind::LargeNewspaper],
+            overlap.filters
+        );
+        assert_eq!(2, overlap.stories);
+        assert_eq!(66.7, overlap.percent);
+
+        let text = stats.to_string();
+        let header = "5 stories stored from 2021-01-02 to 2021-01-05";
+        assert!(text.contains(header));
+        let week = "2021-01-04 (4 stories): amfg 50%, bignews 50%";
+        assert!(text.contains(week));
+        assert!(
+            text.contains("amfg + bignews: 2 stories (66.7% of the smaller)")
+        );
+    }
+
+    #[test]
+    fn it_collects_stats_from_db() -> Result<()> {
+        let conn = db::tests::test_conn()?;
+        let mut google = Story::random_url();
+        google.title = "Google is down".to_string();
+        let stories = [
+            (google, vec![FilterKind::BigTech]),
+            (Story::random_url(), vec![]),
+        ];
+        db::tests::insert_test_data(&conn, &stories)?;
+
+        let stats = collect(&conn, 7, date::now())?;
+        assert_eq!(2, stats.stories);
+        assert_eq!(7, stats.daily.len());
+        assert_eq!(2, stats.daily[6].stories);
+
+        let bigtech = stats
+            .filters
+            .iter()
+            .find(|stats| stats.filter == FilterKind::BigTech)
+            .unwrap();
+        assert_eq!(1, bigtech.flagged);
+        assert_eq!(
+            db::tests::test_reason(FilterKind::BigTech),
+            bigtech.top_reasons[0].name
+        );
+
+        let json = serde_json::to_value(&stats)?;
+        assert_eq!(2, json["stories"]);
+        assert_eq!("amfg", json["weekly"][0]["rates"][1]["filter"]);
+        assert!(json["filters"][1]["topDomains"].is_array());
+
+        Ok(())
+    }
+}
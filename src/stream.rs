@@ -0,0 +1,354 @@
+//! HN Firebase endpoints support [server-sent events][firebase-sse]. Instead
+//! of polling the top stories endpoint we can subscribe to it and get notified
+//! whenever the front page changes.
+//!
+//! The stream sends `put` and `patch` events. Each event has a json payload
+//! with a `path` into the subscribed document and the `data` to write there.
+//! The top stories document is a list, hence paths are either `/` for the whole
+//! list or `/${INDEX}` for a single position.
+//!
+//! [firebase-sse]: https://firebase.google.com/docs/reference/rest/database#section-streaming
+
+use {
+    reqwest::{header, Client, Response},
+    serde::Deserialize,
+    serde_json::Value,
+    std::{io, time::Duration},
+    tokio::time,
+};
+
+use crate::prelude::*;
+
+/// Firebase sends a keep-alive event every 30s. If we don't hear anything for
+/// much longer than that, the connection is most likely dead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Subscription to the top stories list.
+pub struct TopStories {
+    resp: Response,
+    // bytes received but not yet parsed into events, a multi-byte character
+    // can be split across chunks hence we only decode complete events
+    buf: Vec<u8>,
+    ids: Vec<StoryId>,
+}
+
+/// Single message from the event stream.
+#[derive(Debug, PartialEq)]
+struct Event {
+    name: String,
+    data: String,
+}
+
+impl TopStories {
+    /// Opens the event stream. Mind that the client must not have a total
+    /// request timeout set as the response body never ends.
+    pub async fn connect(client: &Client, url: &str) -> Result<Self> {
+        let resp = client
+            .get(url)
+            .header(header::ACCEPT, "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(Self {
+            resp,
+            buf: Vec::new(),
+            ids: Vec::new(),
+        })
+    }
+
+    /// Waits until the top stories list changes and returns it. Returns `None`
+    /// if the server closed the stream.
+    ///
+    /// It's safe to cancel this future, no events are lost.
+    pub async fn next(&mut self) -> Result<Option<Vec<StoryId>>> {
+        loop {
+            let chunk = time::timeout(IDLE_TIMEOUT, self.resp.chunk())
+                .await
+                .map_err(|_| {
+                io::Error::new(io::ErrorKind::TimedOut, "Stream is idle")
+            })??;
+
+            let chunk = match chunk {
+                Some(chunk) => chunk,
+                None => return Ok(None),
+            };
+            self.buf.extend_from_slice(&chunk);
+
+            let mut changed = false;
+            for event in drain_events(&mut self.buf) {
+                changed |= apply(&mut self.ids, event)?;
+            }
+
+            if changed {
+                return Ok(Some(self.ids.clone()));
+            }
+        }
+    }
+}
+
+// Writes the event into the list of ids. Returns whether the list changed.
+fn apply(ids: &mut Vec<StoryId>, event: Event) -> Result<bool> {
+    #[derive(Deserialize)]
+    struct Payload {
+        path: String,
+        data: Value,
+    }
+
+    match event.name.as_str() {
+        "put" | "patch" => (),
+        "keep-alive" => return Ok(false),
+        "cancel" | "auth_revoked" => {
+            return Err(
+                format!("Stream closed by server: {}", event.name).into()
+            )
+        }
+        other => {
+            log::debug!("Ignoring unknown stream event {}", other);
+            return Ok(false);
+        }
+    };
+
+    let Payload { path, data } = serde_json::from_str(&event.data)?;
+    let before = ids.clone();
+
+    match (event.name.as_str(), path.trim_matches('/')) {
+        // whole list is replaced
+        ("put", "") => match data {
+            Value::Null => ids.clear(),
+            Value::Array(_) => *ids = serde_json::from_value(data)?,
+            // firebase sometimes sends sparse lists as objects
+            Value::Object(map) => {
+                ids.clear();
+                write(ids, positions(map)?);
+            }
+            _ => return Err("Unexpected top stories payload".into()),
+        },
+        // selected positions are replaced
+        ("patch", "") => match data {
+            Value::Object(map) => write(ids, positions(map)?),
+            _ => return Err("Unexpected top stories patch".into()),
+        },
+        // single position is replaced
+        (_, index) => write(ids, vec![(index.parse()?, data)]),
+    };
+
+    Ok(before != *ids)
+}
+
+// Object keys are list positions. Mind that the keys of a json object are not
+// ordered by their numeric value, "10" comes before "2".
+fn positions(
+    map: serde_json::Map<String, Value>,
+) -> Result<Vec<(usize, Value)>> {
+    let mut positions = map
+        .into_iter()
+        .map(|(index, data)| Ok((index.parse()?, data)))
+        .collect::<Result<Vec<_>>>()?;
+    positions.sort_by_key(|(index, _)| *index);
+
+    Ok(positions)
+}
+
+// Sets ids at given positions. Null data removes the id, which firebase only
+// does for the tail of the list.
+fn write(ids: &mut Vec<StoryId>, positions: Vec<(usize, Value)>) {
+    let len = positions
+        .iter()
+        .filter(|(_, data)| data.is_i64())
+        .map(|(index, _)| index + 1)
+        .max()
+        .unwrap_or_default();
+    if len > ids.len() {
+        ids.resize(len, 0);
+    }
+
+    for (index, data) in positions {
+        if let Some(slot) = ids.get_mut(index) {
+            *slot = data.as_i64().unwrap_or(0);
+        }
+    }
+
+    // removed ids leave placeholders, and so would positions which the server
+    // sent out of order across events
+    ids.retain(|id| *id != 0);
+}
+
+// Removes all complete events from the buffer. An event is terminated by an
+// empty line.
+fn drain_events(buf: &mut Vec<u8>) -> Vec<Event> {
+    // "\r\n" line endings become "\n", a carriage return byte is never a part
+    // of a multi-byte character
+    buf.retain(|b| *b != b'\r');
+
+    let mut events = vec![];
+    while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+        let raw: Vec<_> = buf.drain(..end + 2).collect();
+        let raw = String::from_utf8_lossy(&raw);
+
+        let mut name = String::from("message");
+        let mut data = Vec::new();
+        for line in raw.lines() {
+            if let Some(value) = line.strip_prefix("event:") {
+                name = value.trim().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.trim_start());
+            }
+        }
+
+        events.push(Event {
+            name,
+            data: data.join("\n"),
+        });
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::stand_in::{Response, StandIn},
+    };
+
+    #[test]
+    fn it_drains_events_split_into_chunks() {
+        let mut buf = b"event: put\ndata: {\"path\":\"/\",".to_vec();
+        assert!(drain_events(&mut buf).is_empty());
+
+        buf.extend_from_slice(
+            b"\"data\":[1]}\n\nevent: keep-alive\r\ndata: null\r\n\r\n",
+        );
+        buf.extend_from_slice(b"event: patch\n");
+        let events = drain_events(&mut buf);
+
+        assert_eq!(
+            vec![
+                Event {
+                    name: "put".to_string(),
+                    data: "{\"path\":\"/\",\"data\":[1]}".to_string(),
+                },
+                Event {
+                    name: "keep-alive".to_string(),
+                    data: "null".to_string(),
+                },
+            ],
+            events
+        );
+        assert_eq!(b"event: patch\n", buf.as_slice());
+    }
+
+    #[test]
+    fn it_decodes_characters_split_into_chunks() {
+        let raw = "event: put\ndata: \"café\"\n\n".as_bytes();
+        let split_at = raw.iter().position(|b| *b > 0x7f).unwrap() + 1;
+
+        let mut buf = raw[..split_at].to_vec();
+        assert!(drain_events(&mut buf).is_empty());
+        buf.extend_from_slice(&raw[split_at..]);
+
+        assert_eq!(vec![event("put", "\"café\"")], drain_events(&mut buf));
+        assert!(buf.is_empty());
+    }
+
+    fn event(name: &str, data: &str) -> Event {
+        Event {
+            name: name.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_puts_sparse_list_in_index_order() -> Result<()> {
+        let mut ids = vec![];
+
+        let sparse = (0..12)
+            .rev()
+            .map(|index| format!("\"{}\":{}", index, 100 + index))
+            .collect::<Vec<_>>()
+            .join(",");
+        let data = format!("{{\"path\":\"/\",\"data\":{{{}}}}}", sparse);
+        assert!(apply(&mut ids, event("put", &data))?);
+
+        assert_eq!((100..112).collect::<Vec<_>>(), ids);
+
+        Ok(())
+    }
+
+    #[test]
+    fn it_patches_positions_out_of_order() -> Result<()> {
+        let mut ids: Vec<_> = (1..=12).collect();
+
+        // truncates the tail and sets positions given in any order
+        let data = concat!(
+            "{\"path\":\"/\",\"data\":",
+            "{\"11\":null,\"10\":null,\"2\":30,\"9\":90,\"0\":10}}"
+        );
+        assert!(apply(&mut ids, event("patch", data))?);
+        assert_eq!(vec![10, 2, 30, 4, 5, 6, 7, 8, 9, 90], ids);
+
+        // appends past the end
+        let data = "{\"path\":\"/\",\"data\":{\"11\":12,\"10\":11}}";
+        assert!(apply(&mut ids, event("patch", data))?);
+        assert_eq!(vec![10, 2, 30, 4, 5, 6, 7, 8, 9, 90, 11, 12], ids);
+
+        // same id at the same position
+        let data = "{\"path\":\"/3\",\"data\":4}";
+        assert!(!apply(&mut ids, event("put", data))?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_follows_top_stories() -> Result<()> {
+        let stand_in = StandIn::start(|_| {
+            Response::ok(concat!(
+                "event: put\n",
+                "data: {\"path\":\"/\",\"data\":[1,2,3]}\n\n",
+                "event: keep-alive\n",
+                "data: null\n\n",
+                "event: put\n",
+                "data: {\"path\":\"/1\",\"data\":9}\n\n",
+                "event: patch\n",
+                "data: {\"path\":\"/\",\"data\":{\"0\":7,\"3\":4}}\n\n",
+                "event: put\n",
+                "data: {\"path\":\"/2\",\"data\":3}\n\n",
+                "event: put\n",
+                "data: {\"path\":\"/3\",\"data\":null}\n\n",
+            ))
+            .header("Content-Type", "text/event-stream")
+        });
+        let url = format!("{}/v0/topstories.json", stand_in.url);
+
+        let mut stream = TopStories::connect(&Client::new(), &url).await?;
+
+        // depending on how the body is chunked we might get intermediate
+        // states, but the last one must reflect all events
+        let mut latest = None;
+        while let Some(ids) = stream.next().await? {
+            latest = Some(ids);
+        }
+        assert_eq!(Some(vec![7, 9, 3]), latest);
+
+        let requests = stand_in.requests();
+        assert_eq!(1, requests.len());
+        assert_eq!("/v0/topstories.json", requests[0].path);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_cancelled_stream() -> Result<()> {
+        let stand_in = StandIn::start(|_| {
+            Response::ok("event: cancel\ndata: null\n\n")
+                .header("Content-Type", "text/event-stream")
+        });
+
+        let mut stream =
+            TopStories::connect(&Client::new(), &stand_in.url).await?;
+        assert!(stream.next().await.is_err());
+
+        Ok(())
+    }
+}